@@ -0,0 +1,83 @@
+//! Compares the child-storage backends on insert, contains and memory.
+//!
+//! Run with `cargo bench --bench backends`. The memory table is printed
+//! before the timings.
+
+use criterion::{criterion_group, BenchmarkId, Criterion};
+use std::hint::black_box;
+use trie_ferris::store::{AsciiLower, ChildStore, Hashed, Ordered, SortedVec};
+use trie_ferris::Trie;
+
+const WORDS: usize = 20_000;
+
+/// Deterministic lowercase words of 3 to 12 letters, from a xorshift
+/// generator so every run sees the same dictionary.
+fn words() -> Vec<String> {
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+
+    (0..WORDS)
+        .map(|_| {
+            let len = 3 + next() % 10;
+            (0..len)
+                .map(|_| (b'a' + (next() % 26) as u8) as char)
+                .collect()
+        })
+        .collect()
+}
+
+fn build<S: ChildStore<char>>(words: &[String]) -> Trie<char, S> {
+    words.iter().collect()
+}
+
+fn report_memory(words: &[String]) {
+    fn row<S: ChildStore<char>>(name: &str, words: &[String]) {
+        let stats = build::<S>(words).stats();
+        println!("{name:<12}{:>12}{:>14}", stats.nodes, stats.heap_bytes);
+    }
+
+    println!("{:<12}{:>12}{:>14}", "backend", "nodes", "heap bytes");
+    row::<Hashed>("hashed", words);
+    row::<SortedVec>("sorted-vec", words);
+    row::<Ordered>("btree", words);
+    row::<AsciiLower>("ascii-26", words);
+    println!();
+}
+
+fn bench_backend<S: ChildStore<char>>(c: &mut Criterion, name: &str, words: &[String]) {
+    c.bench_with_input(BenchmarkId::new("insert", name), &words, |b, words| {
+        b.iter(|| build::<S>(black_box(words)))
+    });
+
+    let trie = build::<S>(words);
+    c.bench_with_input(BenchmarkId::new("contains", name), &words, |b, words| {
+        b.iter(|| {
+            words
+                .iter()
+                .filter(|word| trie.contains(black_box(word.as_str())))
+                .count()
+        })
+    });
+}
+
+fn backends(c: &mut Criterion) {
+    let words = words();
+
+    bench_backend::<Hashed>(c, "hashed", &words);
+    bench_backend::<SortedVec>(c, "sorted-vec", &words);
+    bench_backend::<Ordered>(c, "btree", &words);
+    bench_backend::<AsciiLower>(c, "ascii-26", &words);
+}
+
+criterion_group!(benches, backends);
+
+fn main() {
+    report_memory(&words());
+    benches();
+    Criterion::default().configure_from_args().final_summary();
+}
@@ -0,0 +1,719 @@
+use crate::{FxHashMap, TNode, Trie};
+use std::collections::VecDeque;
+
+#[cfg(feature = "unicode")]
+use crate::Normalization;
+#[cfg(feature = "unicode")]
+use unicode_normalization::char::is_combining_mark;
+
+const NONE: u32 = u32::MAX;
+
+/// A match of a pattern in a haystack, as byte offsets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Match {
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    goto: FxHashMap<char, u32>,
+    fail: u32,
+    /// Nearest state along the failure chain that ends a pattern.
+    output: u32,
+    /// Byte length of the path to this state.
+    len: usize,
+    is_end: bool,
+}
+
+/// An Aho–Corasick automaton compiled from the words of a [`Trie`].
+///
+/// Its states mirror the trie nodes, plus failure links to the longest
+/// proper suffix that is also a path and output links to the nearest
+/// such suffix ending a word. A haystack is scanned in a single pass.
+///
+/// Built from a normalizing trie, it normalizes the haystack the same way:
+/// each char together with the combining marks after it. Offsets still
+/// point into the haystack, and a match must cover whole such runs.
+#[derive(Debug)]
+pub struct AhoCorasick {
+    states: Vec<State>,
+    #[cfg(feature = "unicode")]
+    normalization: Option<Normalization>,
+}
+
+impl From<&Trie> for AhoCorasick {
+    fn from(trie: &Trie) -> Self {
+        Self::new(trie)
+    }
+}
+
+impl AhoCorasick {
+    pub fn new(trie: &Trie) -> Self {
+        // first pass: number the trie nodes breadth-first
+        let mut states = vec![State {
+            output: NONE,
+            ..Default::default()
+        }];
+        let mut queue: VecDeque<(&TNode, u32)> = VecDeque::from([(&trie.root, 0)]);
+        let mut order = Vec::new();
+
+        while let Some((node, id)) = queue.pop_front() {
+            order.push(id);
+
+            for (&ch, child) in &node.children {
+                let next = states.len() as u32;
+                states.push(State {
+                    len: states[id as usize].len + ch.len_utf8(),
+                    is_end: child.is_end(),
+                    output: NONE,
+                    ..Default::default()
+                });
+                states[id as usize].goto.insert(ch, next);
+                queue.push_back((child, next));
+            }
+        }
+
+        let mut automaton = Self {
+            states,
+            #[cfg(feature = "unicode")]
+            normalization: trie.normalization(),
+        };
+
+        // second pass: failure links only point to shallower states, which
+        // the breadth-first order has already linked
+        for id in order {
+            let edges: Vec<_> = automaton.states[id as usize]
+                .goto
+                .iter()
+                .map(|(&ch, &next)| (ch, next))
+                .collect();
+
+            for (ch, next) in edges {
+                let fail = if id == 0 {
+                    0
+                } else {
+                    automaton.step(automaton.states[id as usize].fail, ch)
+                };
+
+                let output = if automaton.states[fail as usize].is_end {
+                    fail
+                } else {
+                    automaton.states[fail as usize].output
+                };
+
+                let state = &mut automaton.states[next as usize];
+                state.fail = fail;
+                state.output = output;
+            }
+        }
+
+        automaton
+    }
+
+    /// Follows `ch` from `state`, falling back along failure links.
+    fn step(&self, mut state: u32, ch: char) -> u32 {
+        loop {
+            let current = &self.states[state as usize];
+
+            if let Some(&next) = current.goto.get(&ch) {
+                return next;
+            }
+
+            if state == 0 {
+                return 0;
+            }
+
+            state = current.fail;
+        }
+    }
+
+    /// The longest match ending at byte offset `end` in `state`.
+    fn longest_match(&self, state: u32, end: usize) -> Option<Match> {
+        let current = &self.states[state as usize];
+        let output = if current.is_end {
+            state
+        } else {
+            current.output
+        };
+
+        (output != NONE).then(|| Match {
+            start: end - self.states[output as usize].len,
+            end,
+        })
+    }
+
+    /// Reports every match ending at byte offset `end` in `state`.
+    fn emit(&self, state: u32, end: usize, matches: &mut Vec<Match>) {
+        let current = &self.states[state as usize];
+        let mut output = if current.is_end {
+            state
+        } else {
+            current.output
+        };
+
+        while output != NONE {
+            let state = &self.states[output as usize];
+            matches.push(Match {
+                start: end - state.len,
+                end,
+            });
+            output = state.output;
+        }
+    }
+
+    /// Finds every occurrence of every word, including overlapping ones,
+    /// ordered by end offset and then longest first.
+    pub fn find_overlapping(&self, haystack: &str) -> Vec<Match> {
+        self.find(haystack, Search::new(false))
+    }
+
+    /// Finds non-overlapping matches, preferring the leftmost start and
+    /// then the longest word at that start.
+    pub fn find_leftmost_longest(&self, haystack: &str) -> Vec<Match> {
+        self.find(haystack, Search::new(true))
+    }
+
+    fn find(&self, haystack: &str, mut search: Search) -> Vec<Match> {
+        // the scanner keeps the offsets of the normalized text apart
+        #[cfg(feature = "unicode")]
+        if self.normalization.is_some() {
+            let mut scanner = StreamScanner::new(self, search);
+            let mut matches = scanner.feed(haystack.as_bytes());
+            matches.extend(scanner.finish());
+            return matches;
+        }
+
+        let mut matches = Vec::new();
+
+        for (i, ch) in haystack.char_indices() {
+            search.next(self, ch, i + ch.len_utf8(), &mut matches);
+        }
+
+        search.finish(self, &mut matches);
+        matches
+    }
+
+    /// Starts an overlapping search over input that arrives in chunks.
+    pub fn stream(&self) -> StreamScanner<'_> {
+        StreamScanner::new(self, Search::new(false))
+    }
+
+    /// Starts a leftmost-longest search over input that arrives in chunks.
+    pub fn stream_leftmost_longest(&self) -> StreamScanner<'_> {
+        StreamScanner::new(self, Search::new(true))
+    }
+}
+
+/// Scan state shared by the one-shot searches and [`StreamScanner`].
+#[derive(Debug, Clone)]
+struct Search {
+    state: u32,
+    leftmost_longest: bool,
+    /// Best leftmost-longest match so far, held back until no later match
+    /// can start at or before it.
+    candidate: Option<Match>,
+    /// Chars read past the end of `candidate`. The scan restarts right
+    /// after a reported match, so these are read again.
+    tail: Vec<char>,
+}
+
+impl Search {
+    fn new(leftmost_longest: bool) -> Self {
+        Self {
+            state: 0,
+            leftmost_longest,
+            candidate: None,
+            tail: Vec::new(),
+        }
+    }
+
+    /// Steps over `ch`, which ends at byte offset `end`, and pushes the
+    /// matches that became final.
+    fn next(&mut self, automaton: &AhoCorasick, ch: char, end: usize, matches: &mut Vec<Match>) {
+        if !self.leftmost_longest {
+            self.state = automaton.step(self.state, ch);
+            automaton.emit(self.state, end, matches);
+        } else if let Some(found) = self.advance(automaton, ch, end) {
+            self.report(automaton, found, matches);
+        }
+    }
+
+    /// Leftmost-longest step, returning the candidate once it is final.
+    fn advance(&mut self, automaton: &AhoCorasick, ch: char, end: usize) -> Option<Match> {
+        self.state = automaton.step(self.state, ch);
+
+        if let Some(candidate) = self.candidate {
+            self.tail.push(ch);
+
+            // every match from here on starts inside the current path
+            if candidate.start < end - automaton.states[self.state as usize].len {
+                self.candidate = None;
+                return Some(candidate);
+            }
+        }
+
+        if let Some(longest) = automaton.longest_match(self.state, end) {
+            if self.candidate.is_none_or(|c| longest.start <= c.start) {
+                self.candidate = Some(longest);
+                self.tail.clear();
+            }
+        }
+
+        None
+    }
+
+    /// Pushes `found` and scans the tail again from the root, which may
+    /// report further matches in turn.
+    fn report(&mut self, automaton: &AhoCorasick, found: Match, matches: &mut Vec<Match>) {
+        let mut found = Some(found);
+
+        while let Some(current) = found.take() {
+            matches.push(current);
+            self.state = 0;
+
+            let tail = std::mem::take(&mut self.tail);
+            let mut end = current.end;
+
+            for (i, &ch) in tail.iter().enumerate() {
+                end += ch.len_utf8();
+                found = self.advance(automaton, ch, end);
+
+                if found.is_some() {
+                    self.tail.extend_from_slice(&tail[i + 1..]);
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Ends a run of text, pushing the matches still held back.
+    fn finish(&mut self, automaton: &AhoCorasick, matches: &mut Vec<Match>) {
+        while let Some(candidate) = self.candidate.take() {
+            self.report(automaton, candidate, matches);
+        }
+
+        self.state = 0;
+    }
+}
+
+/// Incremental search, see [`AhoCorasick::stream`] and
+/// [`AhoCorasick::stream_leftmost_longest`].
+///
+/// Chunks are raw bytes and may split a char, the partial sequence is
+/// kept until the next chunk. Invalid UTF-8 is skipped and breaks any
+/// match running across it. Offsets count from the start of the stream.
+///
+/// A leftmost-longest match is only returned once no other match can
+/// replace it, which may be several chunks after it ends; call
+/// [`finish`](Self::finish) at the end of the stream for the last ones.
+/// With a normalizing automaton, a char is only scanned once the next one
+/// shows it has no more combining marks, so every match may come a chunk
+/// late.
+pub struct StreamScanner<'a> {
+    automaton: &'a AhoCorasick,
+    search: Search,
+    offset: usize,
+    pending: Vec<u8>,
+    #[cfg(feature = "unicode")]
+    normalizing: Option<Normalizing>,
+}
+
+impl<'a> StreamScanner<'a> {
+    fn new(automaton: &'a AhoCorasick, search: Search) -> Self {
+        Self {
+            automaton,
+            search,
+            offset: 0,
+            pending: Vec::new(),
+            #[cfg(feature = "unicode")]
+            normalizing: automaton
+                .normalization
+                .map(|policy| Normalizing::new(automaton, policy)),
+        }
+    }
+
+    /// Ends the stream, returning the matches still held back. Bytes of
+    /// an unfinished char are dropped.
+    pub fn finish(mut self) -> Vec<Match> {
+        let mut matches = Vec::new();
+        self.end_run(&mut matches);
+        #[cfg(feature = "unicode")]
+        self.translate(&mut matches);
+        matches
+    }
+
+    /// Steps over `ch`, which ends at byte offset `self.offset`.
+    fn next(&mut self, ch: char, matches: &mut Vec<Match>) {
+        #[cfg(feature = "unicode")]
+        if let Some(normalizing) = &mut self.normalizing {
+            let start = self.offset - ch.len_utf8();
+            normalizing.push(self.automaton, &mut self.search, ch, start, matches);
+            return;
+        }
+
+        self.search.next(self.automaton, ch, self.offset, matches);
+    }
+
+    /// Ends a run of valid text, see [`Search::finish`].
+    fn end_run(&mut self, matches: &mut Vec<Match>) {
+        #[cfg(feature = "unicode")]
+        if let Some(normalizing) = &mut self.normalizing {
+            normalizing.flush(self.automaton, &mut self.search, matches);
+        }
+
+        self.search.finish(self.automaton, matches);
+    }
+
+    /// Moves `matches` from normalized offsets back to stream offsets.
+    #[cfg(feature = "unicode")]
+    fn translate(&mut self, matches: &mut Vec<Match>) {
+        if let Some(normalizing) = &mut self.normalizing {
+            matches.retain_mut(|found| match normalizing.translate(*found) {
+                Some(translated) => {
+                    *found = translated;
+                    true
+                }
+                None => false,
+            });
+            normalizing.forget(&self.search);
+        }
+    }
+
+    /// Scans the next chunk, returning the matches that became final in
+    /// it. For an overlapping search, those are the matches ending in it.
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<Match> {
+        let mut matches = Vec::new();
+        let mut bytes = std::mem::take(&mut self.pending);
+        bytes.extend_from_slice(chunk);
+
+        let mut rest = bytes.as_slice();
+
+        loop {
+            let (valid, skip) = match std::str::from_utf8(rest) {
+                Ok(valid) => (valid, None),
+                Err(err) => {
+                    let valid = std::str::from_utf8(&rest[..err.valid_up_to()]).unwrap_or_default();
+                    (valid, Some(err.error_len()))
+                }
+            };
+
+            for ch in valid.chars() {
+                self.offset += ch.len_utf8();
+                self.next(ch, &mut matches);
+            }
+
+            rest = &rest[valid.len()..];
+
+            match skip {
+                None => break,
+                // an incomplete char, wait for the rest of it
+                Some(None) => {
+                    self.pending = rest.to_vec();
+                    break;
+                }
+                Some(Some(len)) => {
+                    self.end_run(&mut matches);
+                    self.offset += len;
+                    rest = &rest[len..];
+                }
+            }
+        }
+
+        #[cfg(feature = "unicode")]
+        self.translate(&mut matches);
+        matches
+    }
+}
+
+/// Scan state of a normalizing automaton. The search runs over the
+/// normalized text, with its own offsets.
+#[cfg(feature = "unicode")]
+#[derive(Debug)]
+struct Normalizing {
+    policy: Normalization,
+    /// A char and the combining marks read after it so far.
+    unit: String,
+    /// Stream offset `unit` ends at.
+    unit_end: usize,
+    /// Length of the normalized text scanned so far.
+    end: usize,
+    /// Where the normalized form of each unit ended, paired with the
+    /// stream offset of the unit's end. Several units can end at the same
+    /// normalized offset, when they normalize to nothing.
+    boundaries: VecDeque<(usize, usize)>,
+    /// Length of the longest path, which bounds how far back a match can
+    /// start.
+    max_len: usize,
+}
+
+#[cfg(feature = "unicode")]
+impl Normalizing {
+    fn new(automaton: &AhoCorasick, policy: Normalization) -> Self {
+        Self {
+            policy,
+            unit: String::new(),
+            unit_end: 0,
+            end: 0,
+            boundaries: VecDeque::from([(0, 0)]),
+            max_len: automaton
+                .states
+                .iter()
+                .map(|state| state.len)
+                .max()
+                .unwrap_or(0),
+        }
+    }
+
+    /// Adds `ch`, which starts at stream offset `start`, scanning the unit
+    /// before it if `ch` starts a new one.
+    fn push(
+        &mut self,
+        automaton: &AhoCorasick,
+        search: &mut Search,
+        ch: char,
+        start: usize,
+        matches: &mut Vec<Match>,
+    ) {
+        if !is_combining_mark(ch) {
+            self.flush(automaton, search, matches);
+        }
+
+        // after invalid bytes, the next match starts past them
+        if self.unit.is_empty() && self.boundaries.back().map(|&(_, end)| end) != Some(start) {
+            self.boundaries.push_back((self.end, start));
+        }
+
+        self.unit.push(ch);
+        self.unit_end = start + ch.len_utf8();
+    }
+
+    /// Scans the pending unit.
+    fn flush(&mut self, automaton: &AhoCorasick, search: &mut Search, matches: &mut Vec<Match>) {
+        if self.unit.is_empty() {
+            return;
+        }
+
+        for ch in self.policy.apply(&self.unit).chars() {
+            self.end += ch.len_utf8();
+            search.next(automaton, ch, self.end, matches);
+        }
+
+        self.unit.clear();
+        self.boundaries.push_back((self.end, self.unit_end));
+    }
+
+    /// The stream range of a match over normalized text, or `None` if it
+    /// starts or ends inside the normalized form of a unit.
+    fn translate(&self, found: Match) -> Option<Match> {
+        let boundaries = &self.boundaries;
+
+        // a match starts after the units that normalize to nothing, and
+        // ends before them
+        let after = boundaries.partition_point(|&(end, _)| end <= found.start);
+        let (end, start) = boundaries[after.checked_sub(1)?];
+        if end != found.start {
+            return None;
+        }
+
+        let (end, stop) =
+            *boundaries.get(boundaries.partition_point(|&(end, _)| end < found.end))?;
+        (end == found.end).then_some(Match { start, end: stop })
+    }
+
+    /// Drops the boundaries that no match can start at anymore.
+    fn forget(&mut self, search: &Search) {
+        let mut oldest = self.end.saturating_sub(self.max_len);
+
+        if let Some(candidate) = search.candidate {
+            oldest = oldest.min(candidate.start);
+        }
+
+        while self.boundaries.len() > 1 && self.boundaries[0].0 < oldest {
+            self.boundaries.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn automaton(words: &[&str]) -> AhoCorasick {
+        AhoCorasick::new(&words.iter().collect())
+    }
+
+    fn spans<'h>(haystack: &'h str, matches: &[Match]) -> Vec<&'h str> {
+        matches.iter().map(|m| &haystack[m.start..m.end]).collect()
+    }
+
+    #[test]
+    fn test_overlapping() {
+        let ac = automaton(&["he", "she", "his", "hers"]);
+        let haystack = "ushers";
+        let matches = ac.find_overlapping(haystack);
+
+        assert_eq!(spans(haystack, &matches), ["she", "he", "hers"]);
+        assert_eq!(matches[0], Match { start: 1, end: 4 });
+
+        let ac = automaton(&["a", "aa", "aaa"]);
+        assert_eq!(ac.find_overlapping("aaa").len(), 6);
+        assert_eq!(ac.find_overlapping("b"), []);
+    }
+
+    #[test]
+    fn test_leftmost_longest() {
+        let ac = automaton(&["abc", "abcd", "bcde", "cd", "e"]);
+        let haystack = "xabcdef";
+        let matches = ac.find_leftmost_longest(haystack);
+
+        assert_eq!(spans(haystack, &matches), ["abcd", "e"]);
+
+        let ac = automaton(&["error", "err", "timeout", "🦀"]);
+        let haystack = "ERR: err🦀 error: timeout";
+        let matches = ac.find_leftmost_longest(haystack);
+        assert_eq!(spans(haystack, &matches), ["err", "🦀", "error", "timeout"]);
+        assert_eq!(matches[1], Match { start: 8, end: 12 });
+
+        // a shorter word is reported when the longer one it starts fails
+        let ac = automaton(&["abcd", "bc", "cdx", "a"]);
+        let haystack = "abcx abcdx";
+        let matches = ac.find_leftmost_longest(haystack);
+        assert_eq!(spans(haystack, &matches), ["a", "bc", "abcd"]);
+
+        // the scan restarts right after a match, not after the text read
+        let ac = automaton(&["ab", "bcd", "cd"]);
+        let haystack = "abcd";
+        assert_eq!(
+            spans(haystack, &ac.find_leftmost_longest(haystack)),
+            ["ab", "cd"]
+        );
+
+        let ac = automaton(&["a", "aa", "aaa"]);
+        assert_eq!(ac.find_leftmost_longest("aaaaa").len(), 2);
+        assert_eq!(ac.find_leftmost_longest("b"), []);
+    }
+
+    #[test]
+    fn test_stream() {
+        let ac = automaton(&["he", "she", "🦀rs"]);
+        let haystack = "ushe🦀rs she".as_bytes();
+        let expected = ac.find_overlapping("ushe🦀rs she");
+
+        for size in 1..haystack.len() {
+            let mut scanner = ac.stream();
+            let matches: Vec<_> = haystack
+                .chunks(size)
+                .flat_map(|c| scanner.feed(c))
+                .collect();
+            assert_eq!(matches, expected, "chunks of {size}");
+        }
+
+        let mut scanner = ac.stream();
+        assert_eq!(scanner.feed(b"sh"), []);
+        assert_eq!(scanner.finish(), []);
+
+        let mut scanner = ac.stream();
+        let matches = scanner.feed(b"s\xffhe she");
+        assert_eq!(
+            matches,
+            [
+                Match { start: 2, end: 4 },
+                Match { start: 5, end: 8 },
+                Match { start: 6, end: 8 }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_stream_leftmost_longest() {
+        let ac = automaton(&["abcd", "bc", "cdx", "a", "🦀rs", "🦀"]);
+        let text = "abcx abcdx 🦀r🦀rs";
+        let haystack = text.as_bytes();
+        let expected = ac.find_leftmost_longest(text);
+        assert_eq!(spans(text, &expected), ["a", "bc", "abcd", "🦀", "🦀rs"]);
+
+        for size in 1..haystack.len() {
+            let mut scanner = ac.stream_leftmost_longest();
+            let mut matches: Vec<_> = haystack
+                .chunks(size)
+                .flat_map(|c| scanner.feed(c))
+                .collect();
+            matches.extend(scanner.finish());
+            assert_eq!(matches, expected, "chunks of {size}");
+        }
+
+        // a match is held back until nothing longer can replace it
+        let mut scanner = ac.stream_leftmost_longest();
+        assert_eq!(scanner.feed(b"abc"), []);
+        assert_eq!(scanner.feed(b"d"), []);
+        assert_eq!(scanner.finish(), [Match { start: 0, end: 4 }]);
+
+        // the text read past a held-back match is scanned again
+        let mut scanner = ac.stream_leftmost_longest();
+        assert_eq!(scanner.feed(b"abc"), []);
+        assert_eq!(
+            scanner.finish(),
+            [Match { start: 0, end: 1 }, Match { start: 1, end: 3 }]
+        );
+
+        // invalid UTF-8 ends the match in progress
+        let mut scanner = ac.stream_leftmost_longest();
+        assert_eq!(scanner.feed(b"ab\xffcdx"), [Match { start: 0, end: 1 }]);
+        assert_eq!(scanner.finish(), [Match { start: 3, end: 6 }]);
+    }
+
+    #[test]
+    #[cfg(feature = "unicode")]
+    fn test_normalized_search() {
+        use crate::{Form, Normalization};
+
+        let policy = Normalization::new()
+            .form(Form::Nfc)
+            .case_fold(true)
+            .strip_diacritics(true);
+        let mut trie = Trie::with_normalization(policy);
+        for word in ["café", "crème", "ss", "s"] {
+            trie.insert(word);
+        }
+
+        let ac = AhoCorasick::new(&trie);
+        let text = "CAFÉ, Cafe\u{301} crème \u{301}CREME\u{300}\u{301} Straße";
+
+        // the fold of ß holds "s" twice, but neither covers all of it
+        let matches = ac.find_overlapping(text);
+        assert_eq!(
+            spans(text, &matches),
+            [
+                "CAFÉ",
+                "Cafe\u{301}",
+                "crème",
+                "CREME\u{300}\u{301}",
+                "S",
+                "ß"
+            ]
+        );
+        assert_eq!(ac.find_leftmost_longest(text), matches);
+
+        let haystack = text.as_bytes();
+        for size in 1..haystack.len() {
+            let mut scanner = ac.stream_leftmost_longest();
+            let mut found: Vec<_> = haystack
+                .chunks(size)
+                .flat_map(|c| scanner.feed(c))
+                .collect();
+            found.extend(scanner.finish());
+            assert_eq!(found, matches, "chunks of {size}");
+        }
+
+        // invalid UTF-8 ends the unit before it, so the mark after it
+        // stands alone
+        let mut scanner = ac.stream();
+        let mut found = scanner.feed(b"cafe\xff\xcc\x81s");
+        found.extend(scanner.finish());
+        assert_eq!(
+            found,
+            [Match { start: 0, end: 4 }, Match { start: 7, end: 8 }]
+        );
+    }
+}
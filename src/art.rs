@@ -0,0 +1,753 @@
+use crate::ByteTrie;
+use std::array;
+
+/// A node of an [`ArtTrie`].
+///
+/// Leaves keep their whole key (lazy expansion), so a subtree holding a
+/// single key is never expanded into inner nodes.
+#[derive(Debug)]
+enum Node {
+    Leaf(Box<[u8]>),
+    Inner(Box<Inner>),
+}
+
+/// An inner node, reached after matching `prefix` (path compression).
+/// `is_end` marks the key that ends right after the prefix.
+///
+/// Every inner node holds at least two keys, counting `is_end`; deletes
+/// collapse the ones left with a single key.
+#[derive(Debug)]
+struct Inner {
+    prefix: Vec<u8>,
+    is_end: bool,
+    children: Children,
+}
+
+/// Child tables by fan-out, growing and shrinking as bytes come and go.
+#[derive(Debug)]
+enum Children {
+    Node4(Sorted<4>),
+    Node16(Box<Sorted<16>>),
+    Node48(Box<Indexed>),
+    Node256(Box<Direct>),
+}
+
+/// Up to `N` children with their bytes in sorted order.
+#[derive(Debug)]
+struct Sorted<const N: usize> {
+    len: usize,
+    keys: [u8; N],
+    nodes: [Option<Node>; N],
+}
+
+/// Up to 48 children, found through a 256-entry index holding slot + 1.
+#[derive(Debug)]
+struct Indexed {
+    len: usize,
+    index: [u8; 256],
+    nodes: [Option<Node>; 48],
+}
+
+/// One slot per byte.
+#[derive(Debug)]
+struct Direct {
+    len: usize,
+    nodes: [Option<Node>; 256],
+}
+
+impl<const N: usize> Sorted<N> {
+    fn new() -> Self {
+        Self {
+            len: 0,
+            keys: [0; N],
+            nodes: array::from_fn(|_| None),
+        }
+    }
+
+    fn position(&self, byte: u8) -> Result<usize, usize> {
+        self.keys[..self.len].binary_search(&byte)
+    }
+
+    fn insert(&mut self, byte: u8, node: Node) {
+        let Err(i) = self.position(byte) else {
+            unreachable!("byte {byte} is already a child");
+        };
+
+        self.keys.copy_within(i..self.len, i + 1);
+        self.nodes[i..=self.len].rotate_right(1);
+        self.keys[i] = byte;
+        self.nodes[i] = Some(node);
+        self.len += 1;
+    }
+
+    fn remove(&mut self, byte: u8) -> Option<Node> {
+        let i = self.position(byte).ok()?;
+        let node = self.nodes[i].take();
+
+        self.keys.copy_within(i + 1..self.len, i);
+        self.nodes[i..self.len].rotate_left(1);
+        self.len -= 1;
+        node
+    }
+
+    /// Moves the children into a table of another width.
+    fn resize<const M: usize>(&mut self) -> Sorted<M> {
+        let mut sorted = Sorted::new();
+
+        for (byte, node) in self.drain() {
+            sorted.keys[sorted.len] = byte;
+            sorted.nodes[sorted.len] = Some(node);
+            sorted.len += 1;
+        }
+
+        sorted
+    }
+
+    fn drain(&mut self) -> impl Iterator<Item = (u8, Node)> + '_ {
+        let len = std::mem::take(&mut self.len);
+        let keys = self.keys;
+
+        self.nodes[..len]
+            .iter_mut()
+            .enumerate()
+            .filter_map(move |(i, node)| Some((keys[i], node.take()?)))
+    }
+}
+
+impl Indexed {
+    fn new() -> Self {
+        Self {
+            len: 0,
+            index: [0; 256],
+            nodes: array::from_fn(|_| None),
+        }
+    }
+
+    fn insert(&mut self, byte: u8, node: Node) {
+        let slot = self
+            .nodes
+            .iter()
+            .position(Option::is_none)
+            .expect("a Node48 with room has a free slot");
+
+        self.nodes[slot] = Some(node);
+        self.index[byte as usize] = slot as u8 + 1;
+        self.len += 1;
+    }
+
+    fn remove(&mut self, byte: u8) -> Option<Node> {
+        let slot = std::mem::take(&mut self.index[byte as usize]).checked_sub(1)?;
+        self.len -= 1;
+        self.nodes[slot as usize].take()
+    }
+
+    fn drain(&mut self) -> impl Iterator<Item = (u8, Node)> + '_ {
+        self.len = 0;
+        let nodes = &mut self.nodes;
+
+        self.index
+            .iter_mut()
+            .enumerate()
+            .filter_map(move |(byte, slot)| {
+                let slot = std::mem::take(slot).checked_sub(1)?;
+                Some((byte as u8, nodes[slot as usize].take()?))
+            })
+    }
+}
+
+impl Direct {
+    fn drain(&mut self) -> impl Iterator<Item = (u8, Node)> + '_ {
+        self.len = 0;
+
+        self.nodes
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(byte, node)| Some((byte as u8, node.take()?)))
+    }
+}
+
+impl Children {
+    fn len(&self) -> usize {
+        match self {
+            Self::Node4(node) => node.len,
+            Self::Node16(node) => node.len,
+            Self::Node48(node) => node.len,
+            Self::Node256(node) => node.len,
+        }
+    }
+
+    fn get(&self, byte: u8) -> Option<&Node> {
+        match self {
+            Self::Node4(node) => node.nodes[node.position(byte).ok()?].as_ref(),
+            Self::Node16(node) => node.nodes[node.position(byte).ok()?].as_ref(),
+            Self::Node48(node) => {
+                let slot = node.index[byte as usize].checked_sub(1)?;
+                node.nodes[slot as usize].as_ref()
+            }
+            Self::Node256(node) => node.nodes[byte as usize].as_ref(),
+        }
+    }
+
+    fn get_mut(&mut self, byte: u8) -> Option<&mut Node> {
+        match self {
+            Self::Node4(node) => node.nodes[node.position(byte).ok()?].as_mut(),
+            Self::Node16(node) => node.nodes[node.position(byte).ok()?].as_mut(),
+            Self::Node48(node) => {
+                let slot = node.index[byte as usize].checked_sub(1)?;
+                node.nodes[slot as usize].as_mut()
+            }
+            Self::Node256(node) => node.nodes[byte as usize].as_mut(),
+        }
+    }
+
+    /// Adds a child under a byte that has none, growing the table first
+    /// if it is full.
+    fn insert(&mut self, byte: u8, child: Node) {
+        match self {
+            Self::Node4(node) if node.len == 4 => *self = Self::Node16(Box::new(node.resize())),
+            Self::Node16(node) if node.len == 16 => {
+                let mut indexed = Box::new(Indexed::new());
+                for (byte, child) in node.drain() {
+                    indexed.insert(byte, child);
+                }
+                *self = Self::Node48(indexed);
+            }
+            Self::Node48(node) if node.len == 48 => {
+                let mut direct = Box::new(Direct {
+                    len: node.len,
+                    nodes: array::from_fn(|_| None),
+                });
+                for (byte, child) in node.drain() {
+                    direct.nodes[byte as usize] = Some(child);
+                }
+                *self = Self::Node256(direct);
+            }
+            _ => {}
+        }
+
+        match self {
+            Self::Node4(node) => node.insert(byte, child),
+            Self::Node16(node) => node.insert(byte, child),
+            Self::Node48(node) => node.insert(byte, child),
+            Self::Node256(node) => {
+                node.nodes[byte as usize] = Some(child);
+                node.len += 1;
+            }
+        }
+    }
+
+    /// Removes a child, shrinking the table once it is well below the
+    /// capacity of the next smaller one, so an add and remove at the
+    /// boundary don't resize every time.
+    fn remove(&mut self, byte: u8) -> Option<Node> {
+        let child = match self {
+            Self::Node4(node) => node.remove(byte),
+            Self::Node16(node) => node.remove(byte),
+            Self::Node48(node) => node.remove(byte),
+            Self::Node256(node) => {
+                let child = node.nodes[byte as usize].take();
+                node.len -= child.is_some() as usize;
+                child
+            }
+        };
+
+        match self {
+            Self::Node16(node) if node.len <= 3 => *self = Self::Node4(node.resize()),
+            Self::Node48(node) if node.len <= 12 => {
+                let mut sorted = Box::new(Sorted::new());
+                for (byte, child) in node.drain() {
+                    sorted.insert(byte, child);
+                }
+                *self = Self::Node16(sorted);
+            }
+            Self::Node256(node) if node.len <= 37 => {
+                let mut indexed = Box::new(Indexed::new());
+                for (byte, child) in node.drain() {
+                    indexed.insert(byte, child);
+                }
+                *self = Self::Node48(indexed);
+            }
+            _ => {}
+        }
+
+        child
+    }
+
+    /// Takes out the only child of a table holding one.
+    fn take_only(&mut self) -> (u8, Node) {
+        let only = match self {
+            Self::Node4(node) => node.drain().next(),
+            Self::Node16(node) => node.drain().next(),
+            Self::Node48(node) => node.drain().next(),
+            Self::Node256(node) => node.drain().next(),
+        };
+
+        only.expect("table holds one child")
+    }
+
+    /// The children in byte order.
+    fn sorted(&self) -> Vec<(u8, &Node)> {
+        match self {
+            Self::Node4(node) => node
+                .keys
+                .iter()
+                .copied()
+                .zip(node.nodes.iter().flatten())
+                .collect(),
+            Self::Node16(node) => node
+                .keys
+                .iter()
+                .copied()
+                .zip(node.nodes.iter().flatten())
+                .collect(),
+            Self::Node48(node) => (0..=255u8)
+                .filter_map(|byte| {
+                    let slot = node.index[byte as usize].checked_sub(1)?;
+                    Some((byte, node.nodes[slot as usize].as_ref()?))
+                })
+                .collect(),
+            Self::Node256(node) => (0..=255u8)
+                .filter_map(|byte| Some((byte, node.nodes[byte as usize].as_ref()?)))
+                .collect(),
+        }
+    }
+}
+
+fn common_prefix_len(a: &[u8], b: &[u8]) -> usize {
+    a.iter().zip(b).take_while(|(x, y)| x == y).count()
+}
+
+/// An adaptive radix tree over byte strings.
+///
+/// Inner nodes pick the smallest of four layouts (Node4, Node16, Node48,
+/// Node256) that fits their fan-out, chains of single children collapse
+/// into a node prefix, and leaves hold whole keys. Insertion, lookup and
+/// deletion behave like on a [`ByteTrie`]: the empty key is never stored.
+#[derive(Debug, Default)]
+pub struct ArtTrie {
+    root: Option<Node>,
+    len: usize,
+}
+
+impl From<&ByteTrie> for ArtTrie {
+    fn from(trie: &ByteTrie) -> Self {
+        let mut art = Self::new();
+
+        for key in trie.iter() {
+            art.insert(&key);
+        }
+
+        art
+    }
+}
+
+impl ArtTrie {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn insert(&mut self, key: &[u8]) {
+        if key.is_empty() {
+            return;
+        }
+
+        let added = match &mut self.root {
+            Some(node) => Self::insert_rec(node, key, 0),
+            None => {
+                self.root = Some(Node::Leaf(key.into()));
+                true
+            }
+        };
+
+        self.len += added as usize;
+    }
+
+    /// Inserts `key`, whose first `depth` bytes led to `node`. Returns
+    /// whether the key is new.
+    fn insert_rec(node: &mut Node, key: &[u8], depth: usize) -> bool {
+        let inner = match node {
+            Node::Leaf(existing) if **existing == *key => return false,
+            Node::Leaf(existing) => {
+                // lazy expansion ends here: both keys get a node to branch in
+                let common = common_prefix_len(&existing[depth..], &key[depth..]);
+                let end = depth + common;
+                let existing = std::mem::take(existing);
+
+                let mut inner = Inner {
+                    prefix: key[depth..end].to_vec(),
+                    is_end: false,
+                    children: Children::Node4(Sorted::new()),
+                };
+                inner.add(existing, end);
+                inner.add(key.into(), end);
+
+                *node = Node::Inner(Box::new(inner));
+                return true;
+            }
+            Node::Inner(inner) => inner,
+        };
+
+        let common = common_prefix_len(&inner.prefix, &key[depth..]);
+        let end = depth + common;
+
+        if common < inner.prefix.len() {
+            // split the prefix, the old node keeps what follows the branch
+            let mut lower = std::mem::replace(
+                inner,
+                Box::new(Inner {
+                    prefix: key[depth..end].to_vec(),
+                    is_end: false,
+                    children: Children::Node4(Sorted::new()),
+                }),
+            );
+            let rest = lower.prefix.split_off(common);
+            lower.prefix = rest[1..].to_vec();
+
+            inner.children.insert(rest[0], Node::Inner(lower));
+            inner.add(key.into(), end);
+            return true;
+        }
+
+        if end == key.len() {
+            return !std::mem::replace(&mut inner.is_end, true);
+        }
+
+        match inner.children.get_mut(key[end]) {
+            Some(child) => Self::insert_rec(child, key, end + 1),
+            None => {
+                inner.children.insert(key[end], Node::Leaf(key.into()));
+                true
+            }
+        }
+    }
+
+    pub fn contains(&self, key: &[u8]) -> bool {
+        let mut node = self.root.as_ref();
+        let mut depth = 0;
+
+        while let Some(current) = node {
+            let inner = match current {
+                Node::Leaf(leaf) => return **leaf == *key,
+                Node::Inner(inner) => inner,
+            };
+
+            if !key[depth..].starts_with(&inner.prefix) {
+                return false;
+            }
+
+            depth += inner.prefix.len();
+
+            let Some(&byte) = key.get(depth) else {
+                return inner.is_end;
+            };
+
+            node = inner.children.get(byte);
+            depth += 1;
+        }
+
+        false
+    }
+
+    pub fn delete(&mut self, key: &[u8]) {
+        let removed = match &mut self.root {
+            Some(Node::Leaf(leaf)) if **leaf == *key => {
+                self.root = None;
+                true
+            }
+            Some(node) => Self::delete_rec(node, key, 0),
+            None => false,
+        };
+
+        self.len -= removed as usize;
+    }
+
+    fn delete_rec(node: &mut Node, key: &[u8], depth: usize) -> bool {
+        let Node::Inner(inner) = node else {
+            return false;
+        };
+
+        if !key[depth..].starts_with(&inner.prefix) {
+            return false;
+        }
+
+        let end = depth + inner.prefix.len();
+
+        let removed = match key.get(end) {
+            None => std::mem::take(&mut inner.is_end),
+            Some(&byte) => match inner.children.get_mut(byte) {
+                Some(Node::Leaf(leaf)) if **leaf == *key => {
+                    inner.children.remove(byte);
+                    true
+                }
+                Some(child) => Self::delete_rec(child, key, end + 1),
+                None => false,
+            },
+        };
+
+        if removed {
+            Self::collapse(node, &key[..end]);
+        }
+
+        removed
+    }
+
+    /// Replaces an inner node left with a single key by that key: a leaf
+    /// for its own `is_end`, or its only child with the prefix prepended.
+    fn collapse(node: &mut Node, path: &[u8]) {
+        let Node::Inner(inner) = node else {
+            return;
+        };
+
+        match (inner.is_end, inner.children.len()) {
+            (true, 0) => *node = Node::Leaf(path.into()),
+            (false, 1) => {
+                let (byte, child) = inner.children.take_only();
+
+                *node = match child {
+                    Node::Leaf(leaf) => Node::Leaf(leaf),
+                    Node::Inner(mut child) => {
+                        let mut prefix = std::mem::take(&mut inner.prefix);
+                        prefix.push(byte);
+                        prefix.append(&mut child.prefix);
+                        child.prefix = prefix;
+                        Node::Inner(child)
+                    }
+                };
+            }
+            _ => {}
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.root = None;
+        self.len = 0;
+    }
+
+    /// Iterates over the keys in byte order.
+    pub fn iter(&self) -> ArtKeys<'_> {
+        ArtKeys {
+            stack: self.root.iter().map(|node| (node, 0, None)).collect(),
+            path: Vec::new(),
+            remaining: self.len,
+        }
+    }
+}
+
+impl Inner {
+    /// Adds a whole key below this node, whose prefix ends at `end`.
+    fn add(&mut self, key: Box<[u8]>, end: usize) {
+        match key.get(end) {
+            Some(&byte) => self.children.insert(byte, Node::Leaf(key)),
+            None => self.is_end = true,
+        }
+    }
+}
+
+/// Iterator over the keys of an [`ArtTrie`] in byte order, from
+/// [`ArtTrie::iter`].
+pub struct ArtKeys<'a> {
+    /// Nodes left to visit, with the path length before their edge byte.
+    stack: Vec<(&'a Node, usize, Option<u8>)>,
+    path: Vec<u8>,
+    remaining: usize,
+}
+
+impl Iterator for ArtKeys<'_> {
+    type Item = Vec<u8>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((node, len, byte)) = self.stack.pop() {
+            let inner = match node {
+                Node::Leaf(leaf) => {
+                    self.remaining -= 1;
+                    return Some(leaf.to_vec());
+                }
+                Node::Inner(inner) => inner,
+            };
+
+            self.path.truncate(len);
+            self.path.extend(byte);
+            self.path.extend_from_slice(&inner.prefix);
+
+            let len = self.path.len();
+            let children = inner.children.sorted();
+            self.stack.extend(
+                children
+                    .into_iter()
+                    .rev()
+                    .map(|(b, child)| (child, len, Some(b))),
+            );
+
+            // a key comes before every key it prefixes
+            if inner.is_end {
+                self.remaining -= 1;
+                return Some(self.path.clone());
+            }
+        }
+
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl ExactSizeIterator for ArtKeys<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Counts the inner nodes by layout: Node4, Node16, Node48, Node256.
+    fn layouts(art: &ArtTrie) -> [usize; 4] {
+        let mut counts = [0; 4];
+        let mut stack: Vec<_> = art.root.iter().collect();
+
+        while let Some(node) = stack.pop() {
+            if let Node::Inner(inner) = node {
+                let i = match inner.children {
+                    Children::Node4(_) => 0,
+                    Children::Node16(_) => 1,
+                    Children::Node48(_) => 2,
+                    Children::Node256(_) => 3,
+                };
+                counts[i] += 1;
+                stack.extend(inner.children.sorted().into_iter().map(|(_, c)| c));
+            }
+        }
+
+        counts
+    }
+
+    #[test]
+    fn test_art_basics() {
+        let mut art = ArtTrie::new();
+
+        for key in [
+            "romane", "romanus", "romulus", "rubens", "ruber", "rub", "r",
+        ] {
+            art.insert(key.as_bytes());
+        }
+        art.insert(b"rub");
+        art.insert(b"");
+
+        assert_eq!(art.len(), 7);
+        assert!(art.contains(b"rub"));
+        assert!(art.contains(b"r"));
+        assert!(!art.contains(b"ru"));
+        assert!(!art.contains(b"roman"));
+        assert!(!art.contains(b"romanes"));
+        assert!(!art.contains(b""));
+
+        let keys: Vec<_> = art.iter().collect();
+        assert_eq!(keys.first().map(Vec::as_slice), Some(&b"r"[..]));
+        assert!(keys.windows(2).all(|w| w[0] < w[1]));
+
+        art.delete(b"rub");
+        art.delete(b"ru");
+        art.delete(b"r");
+        assert_eq!(art.len(), 5);
+        assert!(!art.contains(b"rub"));
+        assert!(art.contains(b"rubens"));
+
+        art.clear();
+        assert!(art.is_empty());
+        assert_eq!(art.iter().next(), None);
+    }
+
+    #[test]
+    fn test_art_grows_and_shrinks() {
+        let mut art = ArtTrie::new();
+        let keys: Vec<[u8; 2]> = (0..=255).map(|b| [7, b]).collect();
+
+        for (i, key) in keys.iter().enumerate() {
+            art.insert(key);
+
+            let expected = match i + 1 {
+                1 => [0, 0, 0, 0],
+                2..=4 => [1, 0, 0, 0],
+                5..=16 => [0, 1, 0, 0],
+                17..=48 => [0, 0, 1, 0],
+                _ => [0, 0, 0, 1],
+            };
+            assert_eq!(layouts(&art), expected, "after {} keys", i + 1);
+        }
+
+        for key in &keys[..250] {
+            art.delete(key);
+        }
+        assert_eq!(layouts(&art), [0, 1, 0, 0]);
+
+        for key in &keys[250..253] {
+            art.delete(key);
+        }
+        assert_eq!(layouts(&art), [1, 0, 0, 0]);
+        assert_eq!(
+            art.iter().collect::<Vec<_>>(),
+            [[7, 253], [7, 254], [7, 255]]
+        );
+
+        art.delete(&[7, 253]);
+        art.delete(&[7, 254]);
+        assert_eq!(layouts(&art), [0, 0, 0, 0], "a lone key is a leaf");
+        assert!(art.contains(&[7, 255]));
+    }
+
+    #[test]
+    fn test_differential_against_trie() {
+        let mut state = 0x9e37_79b9_7f4a_7c15u64;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+
+        let mut art = ArtTrie::new();
+        let mut trie = ByteTrie::new();
+
+        for round in 0..20_000 {
+            // short keys over a few bytes collide often, the wide first
+            // byte pushes the root through every layout
+            let len = next() % 6;
+            let mut key: Vec<u8> = (0..len)
+                .map(|_| [0, 1, 2, 255][next() as usize % 4])
+                .collect();
+            if next() % 3 == 0 {
+                key.insert(0, next() as u8);
+            }
+
+            match next() % 3 {
+                0 | 1 if round < 12_000 => {
+                    art.insert(&key);
+                    trie.insert(&key);
+                }
+                _ => {
+                    art.delete(&key);
+                    trie.delete(&key);
+                }
+            }
+
+            assert_eq!(art.contains(&key), trie.contains(&key), "key {key:?}");
+            assert_eq!(art.len(), trie.len());
+        }
+
+        assert_eq!(
+            art.iter().collect::<Vec<_>>(),
+            trie.iter().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            ArtTrie::from(&trie).iter().collect::<Vec<_>>(),
+            trie.iter().collect::<Vec<_>>()
+        );
+    }
+}
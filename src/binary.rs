@@ -0,0 +1,439 @@
+//! Binary format of [`Trie::write_to`] and [`Trie::read_from`].
+//!
+//! All integers are little endian. The file starts with a 24 byte header:
+//!
+//! | offset | size | field                                  |
+//! |--------|------|----------------------------------------|
+//! | 0      | 4    | magic `b"TRIE"`                        |
+//! | 4      | 2    | format version, currently `1`          |
+//! | 6      | 2    | reserved, `0`                          |
+//! | 8      | 4    | node count, including the root         |
+//! | 12     | 8    | body length in bytes                   |
+//! | 20     | 4    | FNV-1a 32 checksum of the body         |
+//!
+//! The body lists the nodes in pre-order, children sorted by char. Each
+//! node is four LEB128 varints: its char as a scalar value, a flag byte
+//! (bit 0 set on terminal nodes), its weight and its number of children.
+//!
+//! There is no room for a normalization policy or the spellings it keeps,
+//! so a trie made with [`Trie::with_normalization`] can't be written.
+//! Collect its words into a plain trie to save just those.
+
+use crate::{TNode, Trie, TrieMap};
+use std::fmt;
+use std::io::{self, Read, Write};
+
+const MAGIC: [u8; 4] = *b"TRIE";
+const VERSION: u16 = 1;
+const HEADER_LEN: usize = 24;
+const FLAG_END: u64 = 1;
+
+#[derive(Debug)]
+pub enum ReadError {
+    Io(io::Error),
+    BadMagic([u8; 4]),
+    UnsupportedVersion(u16),
+    /// The reserved header field isn't zero.
+    Reserved(u16),
+    ChecksumMismatch {
+        expected: u32,
+        found: u32,
+    },
+    /// The body ended in the middle of a node.
+    Truncated,
+    /// Bytes were left over after the last node.
+    TrailingBytes,
+    NodeCountMismatch {
+        expected: u32,
+        found: u32,
+    },
+    InvalidChar(u64),
+    /// A varint that doesn't fit its field.
+    Overflow,
+    /// A child offset that doesn't point forward to a node. Only
+    /// [`FrozenTrie::new`](crate::FrozenTrie::new) returns this, since the
+    /// varint format stores no offsets.
+    InvalidOffset(usize),
+    /// A non-terminal leaf, a weight on a non-terminal node, a duplicate
+    /// child or unknown flags.
+    InvalidNode,
+}
+
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "i/o error: {err}"),
+            Self::BadMagic(magic) => write!(f, "bad magic bytes {magic:?}"),
+            Self::UnsupportedVersion(v) => write!(f, "unsupported format version {v}"),
+            Self::Reserved(v) => write!(f, "reserved header field is {v:#06x}, expected 0"),
+            Self::ChecksumMismatch { expected, found } => {
+                write!(
+                    f,
+                    "checksum mismatch: expected {expected:#010x}, found {found:#010x}"
+                )
+            }
+            Self::Truncated => write!(f, "unexpected end of data"),
+            Self::TrailingBytes => write!(f, "trailing bytes after the last node"),
+            Self::NodeCountMismatch { expected, found } => {
+                write!(f, "expected {expected} nodes, found {found}")
+            }
+            Self::InvalidChar(c) => write!(f, "invalid char {c:#x}"),
+            Self::Overflow => write!(f, "integer overflow"),
+            Self::InvalidOffset(offset) => write!(f, "invalid node offset {offset}"),
+            Self::InvalidNode => write!(f, "invalid node"),
+        }
+    }
+}
+
+impl std::error::Error for ReadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ReadError {
+    fn from(err: io::Error) -> Self {
+        match err.kind() {
+            io::ErrorKind::UnexpectedEof => Self::Truncated,
+            _ => Self::Io(err),
+        }
+    }
+}
+
+pub(crate) fn fnv1a(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0x811c_9dc5, |hash, &b| {
+        (hash ^ b as u32).wrapping_mul(0x0100_0193)
+    })
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+
+    out.push(value as u8);
+}
+
+fn read_varint(bytes: &mut &[u8]) -> Result<u64, ReadError> {
+    let mut value = 0u64;
+
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = bytes.split_first().ok_or(ReadError::Truncated)?;
+        *bytes = rest;
+
+        let bits = (byte & 0x7f) as u64;
+        if shift == 63 && bits > 1 {
+            return Err(ReadError::Overflow);
+        }
+
+        value |= bits << shift;
+
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+
+    Err(ReadError::Overflow)
+}
+
+struct Pending {
+    node: TNode,
+    remaining: u64,
+}
+
+fn read_node(body: &mut &[u8]) -> Result<Pending, ReadError> {
+    let value = read_varint(body)?;
+    let value = u32::try_from(value)
+        .ok()
+        .and_then(char::from_u32)
+        .ok_or(ReadError::InvalidChar(value))?;
+    let flags = read_varint(body)?;
+    let weight = read_varint(body)?;
+    let remaining = read_varint(body)?;
+
+    // only terminal nodes carry a weight
+    if flags & !FLAG_END != 0 || (flags & FLAG_END == 0 && weight != 0) {
+        return Err(ReadError::InvalidNode);
+    }
+
+    let mut node = TNode::new(value, (flags & FLAG_END != 0).then_some(()));
+    node.weight = weight;
+
+    Ok(Pending { node, remaining })
+}
+
+impl Trie {
+    /// Writes the trie in the binary format described in the
+    /// [module docs](crate::binary).
+    ///
+    /// Fails with [`io::ErrorKind::InvalidInput`] if the trie has a
+    /// normalization policy.
+    pub fn write_to(&self, mut writer: impl Write) -> io::Result<()> {
+        self.check_serializable()
+            .map_err(|msg| io::Error::new(io::ErrorKind::InvalidInput, msg))?;
+
+        let mut body = Vec::new();
+        let mut count = 0u32;
+        let mut stack = vec![&self.root];
+
+        while let Some(node) = stack.pop() {
+            count = count.checked_add(1).ok_or_else(|| {
+                io::Error::new(io::ErrorKind::FileTooLarge, "more than u32::MAX nodes")
+            })?;
+
+            let mut children: Vec<_> = node.children.values().collect();
+            children.sort_unstable_by_key(|c| c.value);
+
+            write_varint(&mut body, node.value as u64);
+            write_varint(&mut body, if node.is_end() { FLAG_END } else { 0 });
+            write_varint(&mut body, node.weight);
+            write_varint(&mut body, children.len() as u64);
+
+            stack.extend(children.into_iter().rev());
+        }
+
+        let mut header = Vec::with_capacity(HEADER_LEN);
+        header.extend_from_slice(&MAGIC);
+        header.extend_from_slice(&VERSION.to_le_bytes());
+        header.extend_from_slice(&0u16.to_le_bytes());
+        header.extend_from_slice(&count.to_le_bytes());
+        header.extend_from_slice(&(body.len() as u64).to_le_bytes());
+        header.extend_from_slice(&fnv1a(&body).to_le_bytes());
+
+        writer.write_all(&header)?;
+        writer.write_all(&body)
+    }
+
+    /// Reads a trie written by [`write_to`](Self::write_to).
+    ///
+    /// The whole input is validated, malformed data is reported as a
+    /// [`ReadError`] and never panics.
+    pub fn read_from(mut reader: impl Read) -> Result<Self, ReadError> {
+        let mut header = [0u8; HEADER_LEN];
+        reader.read_exact(&mut header)?;
+
+        let magic = [header[0], header[1], header[2], header[3]];
+        if magic != MAGIC {
+            return Err(ReadError::BadMagic(magic));
+        }
+
+        let version = u16::from_le_bytes([header[4], header[5]]);
+        if version != VERSION {
+            return Err(ReadError::UnsupportedVersion(version));
+        }
+
+        let reserved = u16::from_le_bytes([header[6], header[7]]);
+        if reserved != 0 {
+            return Err(ReadError::Reserved(reserved));
+        }
+
+        let expected_count = u32::from_le_bytes(header[8..12].try_into().unwrap());
+        let body_len = u64::from_le_bytes(header[12..20].try_into().unwrap());
+        let expected_sum = u32::from_le_bytes(header[20..24].try_into().unwrap());
+
+        // read through `take` so a bogus length can't allocate up front
+        let mut body = Vec::new();
+        reader.take(body_len).read_to_end(&mut body)?;
+
+        if (body.len() as u64) < body_len {
+            return Err(ReadError::Truncated);
+        }
+
+        let found_sum = fnv1a(&body);
+        if found_sum != expected_sum {
+            return Err(ReadError::ChecksumMismatch {
+                expected: expected_sum,
+                found: found_sum,
+            });
+        }
+
+        let mut bytes = body.as_slice();
+        let mut count = 1u32;
+        let mut stack = vec![read_node(&mut bytes)?];
+
+        let root = loop {
+            let top = stack.last_mut().ok_or(ReadError::InvalidNode)?;
+
+            if top.remaining > 0 {
+                top.remaining -= 1;
+                count = count.checked_add(1).ok_or(ReadError::Overflow)?;
+
+                if count > expected_count {
+                    return Err(ReadError::NodeCountMismatch {
+                        expected: expected_count,
+                        found: count,
+                    });
+                }
+
+                stack.push(read_node(&mut bytes)?);
+                continue;
+            }
+
+            let Some(Pending { mut node, .. }) = stack.pop() else {
+                return Err(ReadError::InvalidNode);
+            };
+
+            node.update_max_weight();
+
+            let Some(parent) = stack.last_mut() else {
+                break node;
+            };
+
+            if node.is_empty() || parent.node.has(&node.value) {
+                return Err(ReadError::InvalidNode);
+            }
+
+            parent.node.children.insert(node.value, node);
+        };
+
+        if !bytes.is_empty() {
+            return Err(ReadError::TrailingBytes);
+        }
+
+        if count != expected_count {
+            return Err(ReadError::NodeCountMismatch {
+                expected: expected_count,
+                found: count,
+            });
+        }
+
+        if root.is_end() {
+            return Err(ReadError::InvalidNode);
+        }
+
+        Ok(Self::from_map(TrieMap::with_root(root)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WORDS: [&str; 7] = ["coal", "cat", "cin", "catch", "cut", "ünïcödé", "🦀"];
+
+    fn encode(trie: &Trie) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        trie.write_to(&mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut trie: Trie = WORDS.iter().copied().collect();
+        trie.insert_weighted("cat", 300);
+
+        let bytes = encode(&trie);
+        assert_eq!(&bytes[..4], b"TRIE");
+
+        let decoded = Trie::read_from(bytes.as_slice()).unwrap();
+        assert_eq!(
+            decoded.iter().collect::<Vec<_>>(),
+            trie.iter().collect::<Vec<_>>()
+        );
+        assert_eq!(decoded.node_count(), trie.node_count());
+        assert_eq!(decoded.weight("cat"), Some(300));
+        assert_eq!(decoded.root.max_weight, 300);
+        assert!(!decoded.contains("ca"));
+
+        let empty = Trie::read_from(encode(&Trie::new()).as_slice()).unwrap();
+        assert_eq!(empty.iter().count(), 0);
+    }
+
+    #[test]
+    #[cfg(feature = "unicode")]
+    fn test_refuses_policy() {
+        use crate::{FrozenTrie, Normalization};
+
+        let mut trie = Trie::with_normalization(Normalization::new().case_fold(true));
+        trie.insert("Cat");
+
+        let err = trie.write_to(Vec::new()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        let err = FrozenTrie::build(&trie).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+        let words: Vec<String> = trie.iter().collect();
+        let plain: Trie = words.iter().collect();
+        let decoded = Trie::read_from(encode(&plain).as_slice()).unwrap();
+        assert_eq!(decoded.iter().collect::<Vec<_>>(), ["cat"]);
+    }
+
+    #[test]
+    fn test_malformed_input() {
+        let trie: Trie = WORDS.iter().copied().collect();
+        let bytes = encode(&trie);
+
+        let mut bad = bytes.clone();
+        bad[0] = b'X';
+        assert!(matches!(
+            Trie::read_from(bad.as_slice()),
+            Err(ReadError::BadMagic(_))
+        ));
+
+        let mut bad = bytes.clone();
+        bad[4] = 9;
+        assert!(matches!(
+            Trie::read_from(bad.as_slice()),
+            Err(ReadError::UnsupportedVersion(9))
+        ));
+
+        let mut bad = bytes.clone();
+        *bad.last_mut().unwrap() ^= 0xff;
+        assert!(matches!(
+            Trie::read_from(bad.as_slice()),
+            Err(ReadError::ChecksumMismatch { .. })
+        ));
+
+        for len in 0..bytes.len() {
+            assert!(
+                Trie::read_from(&bytes[..len]).is_err(),
+                "prefix of {len} bytes"
+            );
+        }
+
+        let mut bad = bytes.clone();
+        bad[7] = 1;
+        assert!(matches!(
+            Trie::read_from(bad.as_slice()),
+            Err(ReadError::Reserved(0x0100))
+        ));
+
+        // a leaf that ends no word
+        assert!(matches!(
+            Trie::read_from(craft(&[0, 0, 0, 1, 'a' as u64, 0, 0, 0]).as_slice()),
+            Err(ReadError::InvalidNode)
+        ));
+
+        // a weight on the non-terminal node "a" of "ab"
+        let weighted = [0, 0, 0, 1, 'a' as u64, 0, 7, 1, 'b' as u64, 1, 0, 0];
+        assert!(matches!(
+            Trie::read_from(craft(&weighted).as_slice()),
+            Err(ReadError::InvalidNode)
+        ));
+
+        let mut valid = weighted;
+        valid[6] = 0;
+        let trie = Trie::read_from(craft(&valid).as_slice()).unwrap();
+        assert_eq!(trie.iter().collect::<Vec<_>>(), ["ab"]);
+    }
+
+    /// Wraps raw node fields, four varints per node, in a valid header.
+    fn craft(fields: &[u64]) -> Vec<u8> {
+        let mut body = Vec::new();
+        for &field in fields {
+            write_varint(&mut body, field);
+        }
+
+        let mut bytes = encode(&Trie::new())[..HEADER_LEN].to_vec();
+        let count = fields.len() as u32 / 4;
+        bytes[8..12].copy_from_slice(&count.to_le_bytes());
+        bytes[12..20].copy_from_slice(&(body.len() as u64).to_le_bytes());
+        bytes[20..24].copy_from_slice(&fnv1a(&body).to_le_bytes());
+        bytes.extend_from_slice(&body);
+        bytes
+    }
+}
@@ -0,0 +1,218 @@
+use crate::{Key, Symbol, Trie};
+use std::hash::{Hash, Hasher};
+use std::sync::PoisonError;
+
+#[cfg(loom)]
+use loom::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+#[cfg(not(loom))]
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+const DEFAULT_SHARDS: usize = 64;
+
+/// A trie that can be shared between threads.
+///
+/// Words are sharded by their first symbol, each shard being a [`Trie`]
+/// behind its own `RwLock`. Lookups only take a read lock on one shard, so
+/// they never block each other, and writers only block the words sharing
+/// their shard. Since a word never leaves its shard, the pruning in
+/// [`delete`](Self::delete) happens entirely under one write lock.
+pub struct ConcurrentTrie<K: Symbol = char> {
+    shards: Vec<RwLock<Trie<K>>>,
+}
+
+impl<K: Symbol> Default for ConcurrentTrie<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Symbol> ConcurrentTrie<K> {
+    pub fn new() -> Self {
+        Self::with_shards(DEFAULT_SHARDS)
+    }
+
+    /// Creates a trie split into `shards` independently locked parts.
+    pub fn with_shards(shards: usize) -> Self {
+        Self {
+            shards: (0..shards.max(1))
+                .map(|_| RwLock::new(Trie::new()))
+                .collect(),
+        }
+    }
+
+    fn shard<Q: Key<K> + ?Sized>(&self, word: &Q) -> &RwLock<Trie<K>> {
+        let mut hasher = fxhash::FxHasher::default();
+        word.symbols().next().hash(&mut hasher);
+        &self.shards[hasher.finish() as usize % self.shards.len()]
+    }
+
+    fn read<Q: Key<K> + ?Sized>(&self, word: &Q) -> RwLockReadGuard<'_, Trie<K>> {
+        self.shard(word)
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write<Q: Key<K> + ?Sized>(&self, word: &Q) -> RwLockWriteGuard<'_, Trie<K>> {
+        self.shard(word)
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn insert<Q: Key<K> + ?Sized>(&self, word: &Q) {
+        self.write(word).insert(word);
+    }
+
+    pub fn contains<Q: Key<K> + ?Sized>(&self, word: &Q) -> bool {
+        self.read(word).contains(word)
+    }
+
+    pub fn delete<Q: Key<K> + ?Sized>(&self, word: &Q) {
+        self.write(word).delete(word);
+    }
+
+    /// Collects the words starting with `prefix`.
+    ///
+    /// An empty prefix visits every shard, one at a time, so the result is
+    /// not an atomic snapshot when writers run concurrently.
+    pub fn words_with_prefix<Q: Key<K> + ?Sized>(&self, prefix: &Q) -> Vec<K::Word> {
+        if prefix.symbols().next().is_some() {
+            return self.read(prefix).words_with_prefix(prefix).collect();
+        }
+
+        self.shards
+            .iter()
+            .flat_map(|shard| {
+                let trie = shard.read().unwrap_or_else(PoisonError::into_inner);
+                trie.iter().collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    pub fn clear(&self) {
+        for shard in &self.shards {
+            shard
+                .write()
+                .unwrap_or_else(PoisonError::into_inner)
+                .clear();
+        }
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn test_many_threads() {
+        let trie = Arc::new(ConcurrentTrie::with_shards(8));
+        let words: Vec<String> = (0..2000).map(|i| format!("w{}x{i}", i % 37)).collect();
+
+        let handles: Vec<_> = (0..8)
+            .map(|t| {
+                let trie = Arc::clone(&trie);
+                let words = words.clone();
+
+                thread::spawn(move || {
+                    for (i, w) in words.iter().enumerate().filter(|(i, _)| i % 8 == t) {
+                        trie.insert(w);
+                        assert!(trie.contains(w));
+
+                        // every third word is deleted again by its writer
+                        if i % 3 == 0 {
+                            trie.delete(w);
+                        }
+                    }
+                })
+            })
+            .chain((0..4).map(|_| {
+                let trie = Arc::clone(&trie);
+                let words = words.clone();
+
+                thread::spawn(move || {
+                    for w in words.iter().step_by(10) {
+                        trie.contains(w);
+                        trie.words_with_prefix(&w[..3]);
+                    }
+                })
+            }))
+            .collect();
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        for (i, w) in words.iter().enumerate() {
+            assert_eq!(trie.contains(w), i % 3 != 0, "{w}");
+        }
+
+        assert_eq!(
+            trie.words_with_prefix("").len(),
+            words.len() - words.len().div_ceil(3)
+        );
+        let mut expected: Vec<_> = words
+            .iter()
+            .enumerate()
+            .filter(|(i, w)| i % 3 != 0 && w.starts_with("w1x"))
+            .map(|(_, w)| w.clone())
+            .collect();
+        let mut found = trie.words_with_prefix("w1x");
+        expected.sort();
+        found.sort();
+        assert_eq!(found, expected);
+
+        trie.clear();
+        assert!(trie.words_with_prefix("").is_empty());
+    }
+}
+
+// RUSTFLAGS="--cfg loom" cargo test --release --lib loom
+#[cfg(all(test, loom))]
+mod loom_tests {
+    use super::*;
+    use loom::sync::Arc;
+    use loom::thread;
+
+    #[test]
+    fn pruning_races_cross_shard_reads() {
+        loom::model(|| {
+            let trie = Arc::new(ConcurrentTrie::with_shards(2));
+            trie.insert("abc");
+            trie.insert("b");
+            assert!(!std::ptr::eq(trie.shard("a"), trie.shard("b")));
+
+            // prunes the 'c' node of "abc" under the first shard's lock
+            let writer = {
+                let trie = Arc::clone(&trie);
+                thread::spawn(move || {
+                    trie.insert("ab");
+                    trie.delete("abc");
+                })
+            };
+
+            // reads both shards, one after the other
+            let reader = {
+                let trie = Arc::clone(&trie);
+                thread::spawn(move || {
+                    let words = trie.words_with_prefix("");
+                    assert!(words.iter().any(|w| w == "ab" || w == "abc"));
+                    assert!(words.iter().any(|w| w == "b" || w == "bd"));
+                    // "abc" only goes after "ab" is in
+                    assert!(trie.contains("abc") || trie.contains("ab"));
+                })
+            };
+
+            trie.insert("bd");
+            trie.delete("b");
+
+            writer.join().unwrap();
+            reader.join().unwrap();
+
+            let mut words = trie.words_with_prefix("");
+            words.sort();
+            assert_eq!(words, ["ab", "bd"]);
+            assert_eq!(trie.words_with_prefix("a"), ["ab"]);
+        });
+    }
+}
@@ -0,0 +1,174 @@
+use crate::store::{ChildStore, Children, Hashed};
+use crate::{Symbol, TNode, TrieMap};
+
+#[cfg(feature = "unicode")]
+use crate::Normalization;
+
+/// A position in a trie, moved one symbol at a time.
+///
+/// Once [`advance`](Self::advance) fails the cursor is dead: it matches
+/// nothing until [`reset`](Self::reset). Clone it to explore several
+/// continuations from the same position.
+///
+/// A cursor from a normalizing [`Trie`](crate::Trie) passes every symbol
+/// through the policy on its own, so a combining mark isn't composed with
+/// the letter before it.
+pub struct Cursor<'a, V = (), K = char, S: ChildStore<K> = Hashed> {
+    root: &'a TNode<V, K, S>,
+    node: Option<&'a TNode<V, K, S>>,
+    depth: usize,
+    #[cfg(feature = "unicode")]
+    normalization: Option<Normalization>,
+}
+
+impl<'a, V, K, S: ChildStore<K>> Clone for Cursor<'a, V, K, S> {
+    fn clone(&self) -> Self {
+        Self {
+            root: self.root,
+            node: self.node,
+            depth: self.depth,
+            #[cfg(feature = "unicode")]
+            normalization: self.normalization,
+        }
+    }
+}
+
+impl<'a, V, K: Symbol, S: ChildStore<K>> Cursor<'a, V, K, S> {
+    pub(crate) fn new(root: &'a TNode<V, K, S>) -> Self {
+        Self {
+            root,
+            node: Some(root),
+            depth: 0,
+            #[cfg(feature = "unicode")]
+            normalization: None,
+        }
+    }
+
+    #[cfg(feature = "unicode")]
+    pub(crate) fn normalizing(mut self, normalization: Option<Normalization>) -> Self {
+        self.normalization = normalization;
+        self
+    }
+
+    /// Moves along `symbol`, returning whether the path still exists.
+    pub fn advance(&mut self, symbol: K) -> bool {
+        #[cfg(feature = "unicode")]
+        if let Some(policy) = self.normalization {
+            // a symbol may normalize to several, or to none at all
+            let symbols = K::normalize(vec![symbol], &policy);
+            return !self.is_dead() && symbols.into_iter().all(|symbol| self.step(symbol));
+        }
+
+        self.step(symbol)
+    }
+
+    fn step(&mut self, symbol: K) -> bool {
+        self.node = self.node.and_then(|node| node.get(&symbol));
+
+        if self.node.is_some() {
+            self.depth += 1;
+        }
+
+        self.node.is_some()
+    }
+
+    /// Checks whether [`advance`](Self::advance) would succeed, without
+    /// moving.
+    pub fn can_advance(&self, symbol: &K) -> bool {
+        #[cfg(feature = "unicode")]
+        if self.normalization.is_some() {
+            return self.clone().advance(symbol.clone());
+        }
+
+        self.node.is_some_and(|node| node.has(symbol))
+    }
+
+    /// Whether the symbols consumed so far form a stored word.
+    pub fn is_word(&self) -> bool {
+        self.node.is_some_and(|node| node.is_end())
+    }
+
+    /// Whether some longer word continues from here.
+    pub fn has_children(&self) -> bool {
+        self.node.is_some_and(|node| !node.children.is_empty())
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.node.is_none()
+    }
+
+    /// Number of symbols matched since the last reset.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// The value of the word ending here, if any.
+    pub fn value(&self) -> Option<&'a V> {
+        self.node?.data.as_ref()
+    }
+
+    pub fn reset(&mut self) {
+        self.node = Some(self.root);
+        self.depth = 0;
+    }
+}
+
+impl<V, K: Symbol, S: ChildStore<K>> TrieMap<V, K, S> {
+    pub fn cursor(&self) -> Cursor<'_, V, K, S> {
+        Cursor::new(&self.root)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Trie, TrieMap};
+
+    #[test]
+    fn test_cursor() {
+        let trie: Trie = ["cat", "catch", "cut"].iter().collect();
+        let mut cursor = trie.cursor();
+
+        assert!(cursor.has_children());
+        assert!(cursor.advance('c'));
+        assert!(cursor.advance('a'));
+        assert!(!cursor.is_word());
+        assert!(cursor.can_advance(&'t'));
+        assert!(!cursor.can_advance(&'x'));
+
+        assert!(cursor.advance('t'));
+        assert!(cursor.is_word());
+        assert!(cursor.has_children());
+        assert_eq!(cursor.depth(), 3);
+
+        let mut branch = cursor.clone();
+        assert!(branch.advance('c'));
+        assert!(branch.advance('h'));
+        assert!(branch.is_word());
+        assert!(!branch.has_children());
+
+        assert!(!cursor.advance('s'));
+        assert!(cursor.is_dead());
+        assert!(!cursor.is_word());
+        assert!(!cursor.advance('c'));
+        assert_eq!(cursor.depth(), 3);
+        assert_eq!(branch.depth(), 5);
+
+        cursor.reset();
+        assert!(!cursor.is_dead());
+        assert!(cursor.advance('c') && cursor.advance('u') && cursor.advance('t'));
+        assert!(cursor.is_word());
+        assert_eq!(cursor.depth(), 3);
+    }
+
+    #[test]
+    fn test_cursor_value() {
+        let mut map = TrieMap::new();
+        map.insert("ab", 1);
+
+        let mut cursor = map.cursor();
+        cursor.advance('a');
+        assert_eq!(cursor.value(), None);
+        cursor.advance('b');
+        assert_eq!(cursor.value(), Some(&1));
+    }
+}
@@ -0,0 +1,357 @@
+use crate::Trie;
+use std::collections::HashMap;
+use std::fmt;
+use std::mem::size_of;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DawgError {
+    /// The input wasn't strictly increasing; holds the offending word.
+    Unsorted(String),
+}
+
+impl fmt::Display for DawgError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unsorted(word) => write!(f, "input is not sorted at \"{word}\""),
+        }
+    }
+}
+
+impl std::error::Error for DawgError {}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+struct State {
+    is_end: bool,
+    /// Outgoing edges, sorted by char.
+    edges: Vec<(char, u32)>,
+}
+
+impl State {
+    fn next(&self, ch: char) -> Option<u32> {
+        let i = self.edges.binary_search_by_key(&ch, |&(c, _)| c).ok()?;
+        Some(self.edges[i].1)
+    }
+
+    fn heap_bytes(&self) -> usize {
+        size_of::<Self>() + self.edges.capacity() * size_of::<(char, u32)>()
+    }
+}
+
+/// Size of a [`Dawg`] as a plain trie and after merging equal suffixes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DawgStats {
+    pub states_before: usize,
+    pub states_after: usize,
+    pub bytes_before: usize,
+    pub bytes_after: usize,
+}
+
+/// A minimal acyclic word graph: a read-only trie whose identical suffix
+/// subtrees are stored once.
+///
+/// It is built incrementally from sorted words, so only the path of the
+/// last inserted word is ever left unminimized.
+#[derive(Debug)]
+pub struct Dawg {
+    states: Vec<State>,
+    len: usize,
+    stats: DawgStats,
+}
+
+impl Dawg {
+    /// Builds the graph from strictly increasing words; empty words are
+    /// skipped like in [`Trie::insert`].
+    pub fn from_sorted<I, S>(words: I) -> Result<Self, DawgError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut builder = Builder::default();
+
+        for word in words {
+            builder.insert(word.as_ref())?;
+        }
+
+        Ok(builder.finish())
+    }
+
+    pub fn contains(&self, word: &str) -> bool {
+        self.find(word)
+            .is_some_and(|state| self.states[state].is_end)
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn stats(&self) -> DawgStats {
+        self.stats
+    }
+
+    /// Iterates over the words starting with `prefix`, in sorted order.
+    pub fn words_with_prefix(&self, prefix: &str) -> DawgWords<'_> {
+        let stack = match self.find(prefix) {
+            Some(state) => vec![(state, prefix.len(), None)],
+            None => Vec::new(),
+        };
+
+        DawgWords {
+            dawg: self,
+            stack,
+            word: prefix.to_string(),
+        }
+    }
+
+    fn find(&self, word: &str) -> Option<usize> {
+        let mut state = 0;
+
+        for ch in word.chars() {
+            state = self.states[state].next(ch)? as usize;
+        }
+
+        Some(state)
+    }
+}
+
+impl From<&Trie> for Dawg {
+    fn from(trie: &Trie) -> Self {
+        let mut builder = Builder::default();
+
+        for word in trie.iter() {
+            // `Trie::iter` is sorted and free of duplicates
+            let _ = builder.insert(&word);
+        }
+
+        builder.finish()
+    }
+}
+
+/// Iterator over the words of a [`Dawg`] below a prefix.
+pub struct DawgWords<'a> {
+    dawg: &'a Dawg,
+    /// `(state, word length before its edge, edge char)`
+    stack: Vec<(usize, usize, Option<char>)>,
+    word: String,
+}
+
+impl<'a> Iterator for DawgWords<'a> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((state, len, ch)) = self.stack.pop() {
+            self.word.truncate(len);
+            self.word.extend(ch);
+
+            let state = &self.dawg.states[state];
+            let len = self.word.len();
+            let edges = state.edges.iter().rev();
+            self.stack
+                .extend(edges.map(|&(c, next)| (next as usize, len, Some(c))));
+
+            if state.is_end {
+                return Some(self.word.clone());
+            }
+        }
+
+        None
+    }
+}
+
+#[derive(Default)]
+struct Builder {
+    states: Vec<State>,
+    register: HashMap<State, u32>,
+    /// Path of the last word that still awaits minimization, as
+    /// `(parent, char, child)` edges.
+    unchecked: Vec<(u32, char, u32)>,
+    previous: String,
+    len: usize,
+    states_before: usize,
+    bytes_before: usize,
+}
+
+impl Builder {
+    fn insert(&mut self, word: &str) -> Result<(), DawgError> {
+        if word.is_empty() {
+            return Ok(());
+        }
+
+        if self.states.is_empty() {
+            self.states.push(State::default());
+        }
+
+        if self.len > 0 && word <= self.previous.as_str() {
+            return Err(DawgError::Unsorted(word.to_string()));
+        }
+
+        let common = word
+            .chars()
+            .zip(self.previous.chars())
+            .take_while(|(a, b)| a == b)
+            .count();
+
+        self.minimize(common);
+
+        let mut state = self.unchecked.last().map_or(0, |&(_, _, child)| child);
+
+        for ch in word.chars().skip(common) {
+            let next = self.states.len() as u32;
+            self.states.push(State::default());
+            self.states[state as usize].edges.push((ch, next));
+            self.unchecked.push((state, ch, next));
+            state = next;
+        }
+
+        self.states[state as usize].is_end = true;
+        self.previous = word.to_string();
+        self.len += 1;
+
+        Ok(())
+    }
+
+    /// Merges the unchecked path below `depth` into the register.
+    fn minimize(&mut self, depth: usize) {
+        while self.unchecked.len() > depth {
+            let Some((parent, ch, child)) = self.unchecked.pop() else {
+                break;
+            };
+
+            // accounted here since every state passes through exactly once
+            self.states_before += 1;
+            self.bytes_before += self.states[child as usize].heap_bytes();
+
+            let state = std::mem::take(&mut self.states[child as usize]);
+
+            let target = match self.register.get(&state) {
+                Some(&existing) => existing,
+                None => {
+                    self.register.insert(state.clone(), child);
+                    self.states[child as usize] = state;
+                    child
+                }
+            };
+
+            let parent = &mut self.states[parent as usize];
+
+            if let Some(edge) = parent.edges.last_mut().filter(|(c, _)| *c == ch) {
+                edge.1 = target;
+            }
+        }
+    }
+
+    fn finish(mut self) -> Dawg {
+        self.minimize(0);
+
+        if self.states.is_empty() {
+            self.states.push(State::default());
+        }
+
+        self.states_before += 1;
+        self.bytes_before += self.states[0].heap_bytes();
+
+        // drop the states that were merged away, renumbering the rest
+        let mut remap = vec![u32::MAX; self.states.len()];
+        let mut order = vec![0];
+        remap[0] = 0;
+
+        let mut i = 0;
+        while i < order.len() {
+            for &(_, next) in &self.states[order[i]].edges {
+                if remap[next as usize] == u32::MAX {
+                    remap[next as usize] = order.len() as u32;
+                    order.push(next as usize);
+                }
+            }
+
+            i += 1;
+        }
+
+        let states: Vec<_> = order
+            .into_iter()
+            .map(|old| {
+                let mut state = std::mem::take(&mut self.states[old]);
+
+                for edge in &mut state.edges {
+                    edge.1 = remap[edge.1 as usize];
+                }
+
+                state
+            })
+            .collect();
+
+        let stats = DawgStats {
+            states_before: self.states_before,
+            states_after: states.len(),
+            bytes_before: self.bytes_before,
+            bytes_after: states.iter().map(State::heap_bytes).sum(),
+        };
+
+        Dawg {
+            states,
+            len: self.len,
+            stats,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WORDS: [&str; 8] = [
+        "tap", "taps", "top", "tops", "walking", "talking", "walked", "talked",
+    ];
+
+    #[test]
+    fn test_from_trie() {
+        let trie: Trie = WORDS.iter().copied().collect();
+        let dawg = Dawg::from(&trie);
+
+        for w in WORDS.iter() {
+            assert!(dawg.contains(w), "should contain \"{}\"", w);
+        }
+
+        assert!(!dawg.contains("ta"));
+        assert!(!dawg.contains("walk"));
+        assert!(!dawg.contains("talkings"));
+        assert_eq!(dawg.len(), WORDS.len());
+
+        assert_eq!(
+            dawg.words_with_prefix("ta").collect::<Vec<_>>(),
+            ["talked", "talking", "tap", "taps"]
+        );
+        assert_eq!(dawg.words_with_prefix("x").count(), 0);
+        assert_eq!(
+            dawg.words_with_prefix("").collect::<Vec<_>>(),
+            trie.iter().collect::<Vec<_>>()
+        );
+
+        let stats = dawg.stats();
+        assert_eq!(stats.states_before, trie.node_count());
+        // "p", "ps", "lking", "lked" and the final state are all shared
+        assert_eq!(stats.states_after, 13);
+        assert!(stats.bytes_after < stats.bytes_before);
+    }
+
+    #[test]
+    fn test_from_sorted() {
+        let dawg = Dawg::from_sorted(["", "a", "ab", "b"]).unwrap();
+        assert!(dawg.contains("ab"));
+        assert!(!dawg.contains(""));
+        assert_eq!(dawg.len(), 3);
+
+        assert_eq!(
+            Dawg::from_sorted(["b", "a"]).unwrap_err(),
+            DawgError::Unsorted("a".to_string())
+        );
+        assert!(Dawg::from_sorted(["a", "a"]).is_err());
+
+        let empty = Dawg::from_sorted(Vec::<String>::new()).unwrap();
+        assert!(empty.is_empty());
+        assert!(!empty.contains("a"));
+    }
+}
@@ -0,0 +1,204 @@
+use crate::map::expect_symbols;
+use crate::store::{ChildStore, Children, Hashed};
+use crate::{Key, Symbol, TNode, TrieMap};
+
+/// A view into a single word of a [`TrieMap`], from [`TrieMap::entry`].
+pub enum Entry<'a, V, K = char, S: ChildStore<K> = Hashed> {
+    Occupied(OccupiedEntry<'a, V, K, S>),
+    Vacant(VacantEntry<'a, V, K, S>),
+}
+
+/// An entry for a word that is stored.
+pub struct OccupiedEntry<'a, V, K = char, S: ChildStore<K> = Hashed> {
+    node: &'a mut TNode<V, K, S>,
+}
+
+/// An entry for a word that isn't stored. It remembers the deepest
+/// existing node on the word's path, so inserting doesn't walk it again.
+pub struct VacantEntry<'a, V, K = char, S: ChildStore<K> = Hashed> {
+    node: &'a mut TNode<V, K, S>,
+    rest: Vec<K>,
+    len: &'a mut usize,
+    empty_word: bool,
+}
+
+impl<'a, V, K: Symbol, S: ChildStore<K>> Entry<'a, V, K, S> {
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        self.or_insert_with(|| default)
+    }
+
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Self::Occupied(entry) => entry.into_mut(),
+            Self::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    pub fn or_default(self) -> &'a mut V
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+
+    pub fn and_modify<F: FnOnce(&mut V)>(mut self, f: F) -> Self {
+        if let Self::Occupied(entry) = &mut self {
+            f(entry.get_mut());
+        }
+
+        self
+    }
+}
+
+impl<'a, V, K, S: ChildStore<K>> OccupiedEntry<'a, V, K, S> {
+    pub fn get(&self) -> &V {
+        self.node
+            .data
+            .as_ref()
+            .expect("occupied entry holds a value")
+    }
+
+    pub fn get_mut(&mut self) -> &mut V {
+        self.node
+            .data
+            .as_mut()
+            .expect("occupied entry holds a value")
+    }
+
+    pub fn into_mut(self) -> &'a mut V {
+        self.node
+            .data
+            .as_mut()
+            .expect("occupied entry holds a value")
+    }
+
+    /// Replaces the value, returning the old one.
+    pub fn insert(&mut self, value: V) -> V {
+        std::mem::replace(self.get_mut(), value)
+    }
+}
+
+impl<'a, V, K: Symbol, S: ChildStore<K>> VacantEntry<'a, V, K, S> {
+    /// # Panics
+    ///
+    /// If the entry is for the empty word, which is never stored, or if
+    /// the word holds a symbol the backend doesn't accept.
+    pub fn insert(self, value: V) -> &'a mut V {
+        assert!(!self.empty_word, "the empty word can't be stored");
+        expect_symbols::<K, S>(self.rest.as_slice());
+        let mut node = self.node;
+
+        for current in self.rest {
+            node = node
+                .children
+                .get_or_insert_with(current.clone(), || TNode::new(current, None));
+        }
+
+        *self.len += 1;
+        node.data.insert(value)
+    }
+}
+
+impl<V, K: Symbol, S: ChildStore<K>> TrieMap<V, K, S> {
+    /// Gets the entry of `word` for in-place insertion or update, walking
+    /// its path only once.
+    ///
+    /// The empty word is never stored, so its entry is always vacant and
+    /// inserting through it panics.
+    pub fn entry<Q: Key<K> + ?Sized>(&mut self, word: &Q) -> Entry<'_, V, K, S> {
+        let symbols: Vec<K> = word.symbols().collect();
+        let (mut node, len) = (&mut self.root, &mut self.len);
+        let mut depth = 0;
+
+        for current in &symbols {
+            if !node.children.contains_key(current) {
+                break;
+            }
+
+            node = node.children.get_mut(current).unwrap();
+            depth += 1;
+        }
+
+        if depth == symbols.len() && node.is_end() {
+            return Entry::Occupied(OccupiedEntry { node });
+        }
+
+        Entry::Vacant(VacantEntry {
+            node,
+            empty_word: symbols.is_empty(),
+            rest: symbols.into_iter().skip(depth).collect(),
+            len,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_word_frequencies() {
+        let mut counts = TrieMap::new();
+
+        for w in "the cat saw the other cat by the door".split(' ') {
+            *counts.entry(w).or_default() += 1;
+        }
+
+        assert_eq!(counts.get("the"), Some(&3));
+        assert_eq!(counts.get("cat"), Some(&2));
+        assert_eq!(counts.get("th"), None);
+        assert_eq!(counts.get("other"), Some(&1));
+        assert_eq!(counts.iter().len(), 6);
+    }
+
+    #[test]
+    fn test_entry_variants() {
+        let mut map = TrieMap::new();
+        map.insert("abc", 1);
+
+        assert!(matches!(map.entry("ab"), Entry::Vacant(_)));
+        assert!(matches!(map.entry("abcd"), Entry::Vacant(_)));
+        assert!(
+            map.find_node("abcd").is_none(),
+            "vacant entries add no nodes"
+        );
+
+        map.entry("abc").and_modify(|v| *v += 10).or_insert(0);
+        map.entry("ab").and_modify(|v| *v += 10).or_insert(5);
+        assert_eq!(map.get("abc"), Some(&11));
+        assert_eq!(map.get("ab"), Some(&5));
+
+        if let Entry::Occupied(mut entry) = map.entry("ab") {
+            assert_eq!(entry.insert(7), 5);
+            assert_eq!(*entry.get(), 7);
+        }
+
+        *map.entry("xyz").or_insert_with(|| 2) *= 3;
+        assert_eq!(map.get("xyz"), Some(&6));
+    }
+
+    #[test]
+    fn test_empty_word_entry() {
+        let mut counts = TrieMap::new();
+
+        for w in "a  b".split(' ').filter(|w| !w.is_empty()) {
+            *counts.entry(w).or_default() += 1;
+        }
+
+        assert!(matches!(counts.entry(""), Entry::Vacant(_)));
+        counts.entry("").and_modify(|v| *v += 1);
+        assert_eq!(counts.get(""), None);
+        assert_eq!(counts.len(), 2);
+        assert_eq!(
+            counts.iter().collect::<Vec<_>>(),
+            [("a".to_string(), &1), ("b".to_string(), &1)]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "the empty word can't be stored")]
+    fn test_empty_word_insert() {
+        let mut counts: TrieMap<u32> = TrieMap::new();
+        counts.entry("").or_insert(1);
+    }
+}
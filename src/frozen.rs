@@ -0,0 +1,473 @@
+//! Read-only trie queried in place over its serialized bytes.
+//!
+//! The layout is meant to be memory mapped. All integers are little endian
+//! `u32`s, the file starts with a 16 byte header:
+//!
+//! | offset | size | field                                  |
+//! |--------|------|----------------------------------------|
+//! | 0      | 4    | magic `b"TFRZ"`                        |
+//! | 4      | 2    | format version, currently `1`          |
+//! | 6      | 2    | reserved, `0`                          |
+//! | 8      | 4    | node count, including the root         |
+//! | 12     | 4    | FNV-1a 32 checksum of everything after |
+//!
+//! The root node follows the header, the other nodes are laid out in
+//! breadth-first order. A node is its flags (bit 0 set on terminal nodes),
+//! its number of children and then one `(char, offset)` pair per child,
+//! sorted by char, where `offset` is the child's position from the start
+//! of the buffer.
+
+use crate::binary::fnv1a;
+use crate::{ReadError, TNode, Trie};
+use std::collections::{HashSet, VecDeque};
+use std::io;
+
+const MAGIC: [u8; 4] = *b"TFRZ";
+const VERSION: u16 = 1;
+const HEADER_LEN: usize = 16;
+const FLAG_END: u32 = 1;
+const NODE_LEN: usize = 8;
+const EDGE_LEN: usize = 8;
+
+/// A trie laid out in a flat byte buffer with offset-based child tables.
+///
+/// [`FrozenTrie::new`] validates the whole buffer once, after which every
+/// query reads the bytes directly without allocating nodes.
+#[derive(Debug, Clone, Copy)]
+pub struct FrozenTrie<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> FrozenTrie<'a> {
+    /// Serializes `trie` into the frozen layout.
+    ///
+    /// Offsets and counts are `u32`s, so this fails with
+    /// [`io::ErrorKind::FileTooLarge`] once the layout passes 4 GiB. Like
+    /// [`Trie::write_to`], it fails with [`io::ErrorKind::InvalidInput`]
+    /// for a trie with a normalization policy.
+    pub fn build(trie: &Trie) -> io::Result<Vec<u8>> {
+        trie.check_serializable()
+            .map_err(|msg| io::Error::new(io::ErrorKind::InvalidInput, msg))?;
+
+        // breadth-first, so every child lands after its parent
+        let mut nodes: Vec<&TNode> = vec![&trie.root];
+        let mut offsets = vec![HEADER_LEN];
+        let mut queue = VecDeque::from([0]);
+        let mut edges: Vec<Vec<(char, usize)>> = vec![Vec::new()];
+
+        while let Some(i) = queue.pop_front() {
+            let mut children: Vec<_> = nodes[i].children.values().collect();
+            children.sort_unstable_by_key(|c| c.value);
+
+            for child in children {
+                let j = nodes.len();
+                let last = offsets[j - 1];
+                offsets.push(last + NODE_LEN + nodes[j - 1].children.len() * EDGE_LEN);
+                nodes.push(child);
+                edges.push(Vec::new());
+                edges[i].push((child.value, j));
+                queue.push_back(j);
+            }
+        }
+
+        let mut body = Vec::new();
+
+        for (node, edges) in nodes.iter().zip(&edges) {
+            let flags = if node.is_end() { FLAG_END } else { 0 };
+            body.extend_from_slice(&flags.to_le_bytes());
+            body.extend_from_slice(&to_u32(edges.len())?.to_le_bytes());
+
+            for &(ch, j) in edges {
+                body.extend_from_slice(&(ch as u32).to_le_bytes());
+                body.extend_from_slice(&to_u32(offsets[j])?.to_le_bytes());
+            }
+        }
+
+        let mut bytes = Vec::with_capacity(HEADER_LEN + body.len());
+        bytes.extend_from_slice(&MAGIC);
+        bytes.extend_from_slice(&VERSION.to_le_bytes());
+        bytes.extend_from_slice(&0u16.to_le_bytes());
+        bytes.extend_from_slice(&to_u32(nodes.len())?.to_le_bytes());
+        bytes.extend_from_slice(&fnv1a(&body).to_le_bytes());
+        bytes.extend_from_slice(&body);
+        Ok(bytes)
+    }
+
+    /// Validates `bytes` and wraps them for querying.
+    ///
+    /// Every node and child offset is checked here, so no later query can
+    /// read out of bounds or loop, however the bytes were corrupted.
+    pub fn new(bytes: &'a [u8]) -> Result<Self, ReadError> {
+        let header = bytes.get(..HEADER_LEN).ok_or(ReadError::Truncated)?;
+
+        let magic = [header[0], header[1], header[2], header[3]];
+        if magic != MAGIC {
+            return Err(ReadError::BadMagic(magic));
+        }
+
+        let version = u16::from_le_bytes([header[4], header[5]]);
+        if version != VERSION {
+            return Err(ReadError::UnsupportedVersion(version));
+        }
+
+        let reserved = u16::from_le_bytes([header[6], header[7]]);
+        if reserved != 0 {
+            return Err(ReadError::Reserved(reserved));
+        }
+
+        let expected_count = read_u32(header, 8);
+        let expected_sum = read_u32(header, 12);
+        let found_sum = fnv1a(&bytes[HEADER_LEN..]);
+
+        if found_sum != expected_sum {
+            return Err(ReadError::ChecksumMismatch {
+                expected: expected_sum,
+                found: found_sum,
+            });
+        }
+
+        // first pass: find where every node starts
+        let mut starts = HashSet::new();
+        let mut offset = HEADER_LEN;
+
+        while offset < bytes.len() {
+            let count = bytes
+                .get(offset + 4..offset + NODE_LEN)
+                .ok_or(ReadError::Truncated)?;
+            let count = read_u32(count, 0) as usize;
+            let len = count
+                .checked_mul(EDGE_LEN)
+                .and_then(|len| len.checked_add(NODE_LEN))
+                .ok_or(ReadError::Overflow)?;
+
+            if bytes.len() - offset < len {
+                return Err(ReadError::Truncated);
+            }
+
+            starts.insert(offset);
+            offset += len;
+        }
+
+        if starts.len() != expected_count as usize || starts.is_empty() {
+            return Err(ReadError::NodeCountMismatch {
+                expected: expected_count,
+                found: starts.len() as u32,
+            });
+        }
+
+        let trie = Self { bytes };
+        let mut referenced = HashSet::with_capacity(starts.len());
+
+        // second pass: every edge is a valid char leading forward to a node
+        // no other edge leads to, so the nodes form a tree
+        for &node in &starts {
+            if trie.flags(node) & !FLAG_END != 0 {
+                return Err(ReadError::InvalidNode);
+            }
+
+            let mut previous = None;
+
+            for (ch, child) in trie.edges(node) {
+                let ch = char::from_u32(ch).ok_or(ReadError::InvalidChar(ch as u64))?;
+
+                if previous.is_some_and(|p| p >= ch) {
+                    return Err(ReadError::InvalidNode);
+                }
+
+                if child <= node || !starts.contains(&child) || !referenced.insert(child) {
+                    return Err(ReadError::InvalidOffset(child));
+                }
+
+                previous = Some(ch);
+            }
+        }
+
+        // the root comes first and can't be referenced, every other node is
+        if referenced.len() != starts.len() - 1 {
+            return Err(ReadError::InvalidNode);
+        }
+
+        Ok(trie)
+    }
+
+    pub fn contains(&self, word: &str) -> bool {
+        self.find(word).is_some_and(|node| self.is_end(node))
+    }
+
+    /// Returns the longest word of the trie that `text` starts with.
+    pub fn longest_prefix<'t>(&self, text: &'t str) -> Option<&'t str> {
+        let mut node = HEADER_LEN;
+        let mut longest = None;
+
+        for (i, ch) in text.char_indices() {
+            match self.child(node, ch) {
+                Some(next) => node = next,
+                None => break,
+            }
+
+            if self.is_end(node) {
+                longest = Some(&text[..i + ch.len_utf8()]);
+            }
+        }
+
+        longest
+    }
+
+    /// Iterates over the words starting with `prefix`, in sorted order.
+    pub fn words_with_prefix(&self, prefix: &str) -> FrozenWords<'a> {
+        let stack = match self.find(prefix) {
+            Some(node) => vec![(node, prefix.len(), None)],
+            None => Vec::new(),
+        };
+
+        FrozenWords {
+            trie: *self,
+            stack,
+            word: prefix.to_string(),
+        }
+    }
+
+    fn find(&self, word: &str) -> Option<usize> {
+        let mut node = HEADER_LEN;
+
+        for ch in word.chars() {
+            node = self.child(node, ch)?;
+        }
+
+        Some(node)
+    }
+
+    fn flags(&self, node: usize) -> u32 {
+        read_u32(self.bytes, node)
+    }
+
+    fn is_end(&self, node: usize) -> bool {
+        self.flags(node) & FLAG_END != 0
+    }
+
+    fn edges(&self, node: usize) -> impl DoubleEndedIterator<Item = (u32, usize)> + '_ {
+        let count = read_u32(self.bytes, node + 4) as usize;
+
+        (0..count).map(move |i| {
+            let edge = node + NODE_LEN + i * EDGE_LEN;
+            (
+                read_u32(self.bytes, edge),
+                read_u32(self.bytes, edge + 4) as usize,
+            )
+        })
+    }
+
+    fn child(&self, node: usize, ch: char) -> Option<usize> {
+        let count = read_u32(self.bytes, node + 4) as usize;
+        let edge = |i: usize| node + NODE_LEN + i * EDGE_LEN;
+        let (mut lo, mut hi) = (0, count);
+
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            let key = read_u32(self.bytes, edge(mid));
+
+            match key.cmp(&(ch as u32)) {
+                std::cmp::Ordering::Less => lo = mid + 1,
+                std::cmp::Ordering::Greater => hi = mid,
+                std::cmp::Ordering::Equal => {
+                    return Some(read_u32(self.bytes, edge(mid) + 4) as usize)
+                }
+            }
+        }
+
+        None
+    }
+}
+
+/// Iterator over the words of a [`FrozenTrie`] below a prefix.
+pub struct FrozenWords<'a> {
+    trie: FrozenTrie<'a>,
+    /// `(node, word length before its edge, edge char)`
+    stack: Vec<(usize, usize, Option<char>)>,
+    word: String,
+}
+
+impl<'a> Iterator for FrozenWords<'a> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((node, len, ch)) = self.stack.pop() {
+            self.word.truncate(len);
+            self.word.extend(ch);
+
+            let len = self.word.len();
+            let edges = self.trie.edges(node).rev();
+            // chars were validated in `FrozenTrie::new`
+            self.stack
+                .extend(edges.filter_map(|(c, next)| Some((next, len, Some(char::from_u32(c)?)))));
+
+            if self.trie.is_end(node) {
+                return Some(self.word.clone());
+            }
+        }
+
+        None
+    }
+}
+
+fn to_u32(n: usize) -> io::Result<u32> {
+    u32::try_from(n).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::FileTooLarge,
+            "frozen layout exceeds the 4 GiB offset range",
+        )
+    })
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    let mut buf = [0; 4];
+    buf.copy_from_slice(&bytes[offset..offset + 4]);
+    u32::from_le_bytes(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WORDS: [&str; 7] = ["coal", "cat", "cin", "catch", "cut", "cit", "🦀"];
+
+    #[test]
+    fn test_queries() {
+        let trie: Trie = WORDS.iter().copied().collect();
+        let bytes = FrozenTrie::build(&trie).unwrap();
+        let frozen = FrozenTrie::new(&bytes).unwrap();
+
+        for w in WORDS.iter() {
+            assert!(frozen.contains(w), "should contain \"{}\"", w);
+        }
+
+        assert!(!frozen.contains("ca"));
+        assert!(!frozen.contains(""));
+        assert!(!frozen.contains("catches"));
+
+        assert_eq!(frozen.longest_prefix("catcher"), Some("catch"));
+        assert_eq!(frozen.longest_prefix("cats"), Some("cat"));
+        assert_eq!(frozen.longest_prefix("🦀🦀"), Some("🦀"));
+        assert_eq!(frozen.longest_prefix("ca"), None);
+
+        assert_eq!(
+            frozen.words_with_prefix("cat").collect::<Vec<_>>(),
+            ["cat", "catch"]
+        );
+        assert_eq!(
+            frozen.words_with_prefix("").collect::<Vec<_>>(),
+            trie.iter().collect::<Vec<_>>()
+        );
+        assert_eq!(frozen.words_with_prefix("x").count(), 0);
+
+        let empty = FrozenTrie::build(&Trie::new()).unwrap();
+        assert!(!FrozenTrie::new(&empty).unwrap().contains("a"));
+    }
+
+    #[test]
+    fn test_corrupt_bytes() {
+        let trie: Trie = WORDS.iter().copied().collect();
+        let bytes = FrozenTrie::build(&trie).unwrap();
+
+        for len in 0..bytes.len() {
+            assert!(
+                FrozenTrie::new(&bytes[..len]).is_err(),
+                "prefix of {len} bytes"
+            );
+        }
+
+        let mut bad = bytes.clone();
+        bad[HEADER_LEN + 12] ^= 1;
+        assert!(matches!(
+            FrozenTrie::new(&bad),
+            Err(ReadError::ChecksumMismatch { .. })
+        ));
+
+        // corrupt every byte with a matching checksum, queries must not panic
+        for i in HEADER_LEN..bytes.len() {
+            for flip in [0x01, 0x80, 0xff] {
+                let mut bad = bytes.clone();
+                bad[i] ^= flip;
+                let sum = fnv1a(&bad[HEADER_LEN..]);
+                bad[12..16].copy_from_slice(&sum.to_le_bytes());
+
+                if let Ok(frozen) = FrozenTrie::new(&bad) {
+                    frozen.contains("catch");
+                    frozen.longest_prefix("catches");
+                    frozen.words_with_prefix("").count();
+                }
+            }
+        }
+    }
+
+    /// Lays out nodes given as flags and `(char, node index)` edges, with a
+    /// valid header and checksum.
+    fn craft(nodes: &[(u32, Vec<(char, usize)>)]) -> Vec<u8> {
+        let mut offsets = vec![HEADER_LEN];
+        for (_, edges) in nodes {
+            let last = *offsets.last().unwrap();
+            offsets.push(last + NODE_LEN + edges.len() * EDGE_LEN);
+        }
+
+        let mut body = Vec::new();
+        for (flags, edges) in nodes {
+            body.extend_from_slice(&flags.to_le_bytes());
+            body.extend_from_slice(&(edges.len() as u32).to_le_bytes());
+
+            for &(ch, j) in edges {
+                body.extend_from_slice(&(ch as u32).to_le_bytes());
+                body.extend_from_slice(&(offsets[j] as u32).to_le_bytes());
+            }
+        }
+
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&VERSION.to_le_bytes());
+        bytes.extend_from_slice(&0u16.to_le_bytes());
+        bytes.extend_from_slice(&(nodes.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&fnv1a(&body).to_le_bytes());
+        bytes.extend_from_slice(&body);
+        bytes
+    }
+
+    #[test]
+    fn test_crafted_graphs() {
+        let tree = craft(&[
+            (0, vec![('a', 1), ('b', 2)]),
+            (FLAG_END, vec![]),
+            (FLAG_END, vec![]),
+        ]);
+        assert!(FrozenTrie::new(&tree).unwrap().contains("b"));
+
+        let mut reserved = tree.clone();
+        reserved[7] = 1;
+        assert!(matches!(
+            FrozenTrie::new(&reserved),
+            Err(ReadError::Reserved(0x0100))
+        ));
+
+        // both edges of every node lead to the next one: 2^39 paths
+        let mut shared: Vec<_> = (0..39)
+            .map(|i| (0, vec![('a', i + 1), ('b', i + 1)]))
+            .collect();
+        shared.push((FLAG_END, vec![]));
+        assert!(matches!(
+            FrozenTrie::new(&craft(&shared)),
+            Err(ReadError::InvalidOffset(_))
+        ));
+
+        // a node no edge leads to
+        let orphan = craft(&[(0, vec![('a', 1)]), (FLAG_END, vec![]), (FLAG_END, vec![])]);
+        assert!(matches!(
+            FrozenTrie::new(&orphan),
+            Err(ReadError::InvalidNode)
+        ));
+    }
+
+    #[test]
+    fn test_size_limit() {
+        assert_eq!(to_u32(u32::MAX as usize).unwrap(), u32::MAX);
+
+        let err = to_u32(u32::MAX as usize + 1).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::FileTooLarge);
+    }
+}
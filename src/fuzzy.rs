@@ -0,0 +1,149 @@
+use crate::iter::sorted_children;
+use crate::{Key, Symbol, TNode, TrieMap};
+
+impl<V, K: Symbol> TrieMap<V, K> {
+    /// Returns every word within `max_distance` Levenshtein edits of
+    /// `query`, paired with its distance.
+    ///
+    /// Matches are ordered by distance, then by word.
+    pub fn fuzzy<Q: Key<K> + ?Sized>(
+        &self,
+        query: &Q,
+        max_distance: usize,
+    ) -> Vec<(K::Word, usize)> {
+        self.fuzzy_search(query, max_distance, false)
+    }
+
+    /// Like [`fuzzy`](Self::fuzzy), but an adjacent transposition counts as
+    /// a single edit (optimal string alignment distance).
+    pub fn fuzzy_damerau<Q: Key<K> + ?Sized>(
+        &self,
+        query: &Q,
+        max_distance: usize,
+    ) -> Vec<(K::Word, usize)> {
+        self.fuzzy_search(query, max_distance, true)
+    }
+
+    fn fuzzy_search<Q: Key<K> + ?Sized>(
+        &self,
+        query: &Q,
+        max_distance: usize,
+        transpositions: bool,
+    ) -> Vec<(K::Word, usize)> {
+        let query: Vec<K> = query.symbols().collect();
+        let mut matches = Vec::new();
+
+        // rows[d] is the edit-distance row of the path at depth d
+        let mut rows = vec![(0..=query.len()).collect::<Vec<_>>()];
+        let mut word: Vec<K> = Vec::new();
+        let mut stack: Vec<(&TNode<V, K>, usize)> = sorted_children(&self.root)
+            .into_iter()
+            .rev()
+            .map(|child| (child, 1))
+            .collect();
+
+        while let Some((node, depth)) = stack.pop() {
+            word.truncate(depth - 1);
+            word.push(node.value.clone());
+
+            let row = next_row(&rows, &query, &word, transpositions);
+            let distance = row[query.len()];
+            let prune = row.iter().min().is_some_and(|&min| min > max_distance);
+
+            rows.truncate(depth);
+            rows.push(row);
+
+            if node.is_end() && distance <= max_distance {
+                matches.push((K::to_word(&word), distance));
+            }
+
+            if !prune {
+                stack.extend(
+                    sorted_children(node)
+                        .into_iter()
+                        .rev()
+                        .map(|child| (child, depth + 1)),
+                );
+            }
+        }
+
+        // the walk visits words in order, the stable sort keeps it per distance
+        matches.sort_by_key(|&(_, distance)| distance);
+        matches
+    }
+}
+
+fn next_row<K: Symbol>(
+    rows: &[Vec<usize>],
+    query: &[K],
+    word: &[K],
+    transpositions: bool,
+) -> Vec<usize> {
+    let depth = word.len();
+    let prev = &rows[depth - 1];
+    let current = &word[depth - 1];
+    let mut row = Vec::with_capacity(prev.len());
+    row.push(prev[0] + 1);
+
+    for j in 1..prev.len() {
+        let cost = (query[j - 1] != *current) as usize;
+        let mut cell = (prev[j] + 1).min(row[j - 1] + 1).min(prev[j - 1] + cost);
+
+        if transpositions
+            && depth > 1
+            && j > 1
+            && query[j - 1] == word[depth - 2]
+            && query[j - 2] == *current
+        {
+            cell = cell.min(rows[depth - 2][j - 2] + 1);
+        }
+
+        row.push(cell);
+    }
+
+    row
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Trie;
+
+    #[test]
+    fn test_fuzzy() {
+        let mut trie = Trie::new();
+
+        for w in ["cat", "cart", "act", "coat", "dog", "cast", "at"] {
+            trie.insert(w);
+        }
+
+        assert_eq!(
+            trie.fuzzy("cat", 1),
+            [
+                ("cat".to_string(), 0),
+                ("at".to_string(), 1),
+                ("cart".to_string(), 1),
+                ("cast".to_string(), 1),
+                ("coat".to_string(), 1),
+            ]
+        );
+        assert_eq!(trie.fuzzy("cta", 0), []);
+        assert_eq!(trie.fuzzy("dgo", 1), []);
+    }
+
+    #[test]
+    fn test_fuzzy_damerau() {
+        let mut trie = Trie::new();
+
+        for w in ["cat", "act", "dog"] {
+            trie.insert(w);
+        }
+
+        assert_eq!(trie.fuzzy("cta", 1), []);
+        assert_eq!(trie.fuzzy_damerau("cta", 1), [("cat".to_string(), 1)]);
+        assert_eq!(
+            trie.fuzzy_damerau("tac", 2),
+            [("act".to_string(), 2), ("cat".to_string(), 2)]
+        );
+        assert_eq!(trie.fuzzy_damerau("dgo", 1), [("dog".to_string(), 1)]);
+    }
+}
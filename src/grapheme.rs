@@ -0,0 +1,131 @@
+use crate::{Symbol, Trie, WordsWithPrefix};
+use std::ops::Deref;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// An extended grapheme cluster used as a trie edge, so that emoji
+/// sequences and letters with combining marks are never split.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Grapheme(String);
+
+impl Grapheme {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Symbol for Grapheme {
+    type Word = String;
+
+    fn to_word(symbols: &[Self]) -> Self::Word {
+        symbols.iter().map(Grapheme::as_str).collect()
+    }
+}
+
+/// Splits `text` into its extended grapheme clusters.
+pub fn graphemes(text: &str) -> Vec<Grapheme> {
+    text.graphemes(true)
+        .map(|g| Grapheme(g.to_string()))
+        .collect()
+}
+
+/// A trie keyed by extended grapheme clusters instead of `char`s.
+///
+/// Prefix queries only match whole clusters, so completions never end in
+/// the middle of a user-perceived character.
+#[derive(Debug, Clone, Default)]
+pub struct GraphemeTrie {
+    trie: Trie<Grapheme>,
+}
+
+impl Deref for GraphemeTrie {
+    type Target = Trie<Grapheme>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.trie
+    }
+}
+
+impl<'a> FromIterator<&'a str> for GraphemeTrie {
+    fn from_iter<I: IntoIterator<Item = &'a str>>(iter: I) -> Self {
+        let mut trie = Self::new();
+
+        for word in iter {
+            trie.insert(word);
+        }
+
+        trie
+    }
+}
+
+impl GraphemeTrie {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, word: &str) {
+        self.trie.insert(&graphemes(word));
+    }
+
+    pub fn contains(&self, word: &str) -> bool {
+        self.trie.contains(&graphemes(word))
+    }
+
+    pub fn delete(&mut self, word: &str) {
+        self.trie.delete(&graphemes(word));
+    }
+
+    pub fn words_with_prefix(&self, prefix: &str) -> WordsWithPrefix<'_, (), Grapheme> {
+        self.trie.words_with_prefix(&graphemes(prefix))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FAMILY: &str = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}";
+
+    #[test]
+    fn test_graphemes_are_edges() {
+        let words = [
+            format!("{FAMILY}!"),
+            FAMILY.to_string(),
+            "e\u{301}te\u{301}".to_string(),
+            "\u{1F1EB}\u{1F1F7}".to_string(),
+        ];
+        let mut trie = GraphemeTrie::new();
+
+        for word in &words {
+            trie.insert(word);
+        }
+
+        assert_eq!(graphemes(FAMILY).len(), 1);
+
+        // one edge per cluster: "é", "t", "é"
+        assert_eq!(
+            trie.find_node(&graphemes("e\u{301}te\u{301}"))
+                .unwrap()
+                .value
+                .as_str(),
+            "e\u{301}"
+        );
+        assert!(trie.contains(FAMILY));
+        assert!(trie.contains("\u{1F1EB}\u{1F1F7}"));
+
+        // partial clusters are not prefixes
+        assert!(!trie.contains("\u{1F1EB}"));
+        assert_eq!(trie.words_with_prefix("\u{1F468}").count(), 0);
+        assert_eq!(trie.words_with_prefix("e").count(), 0);
+
+        let mut completions: Vec<_> = trie.words_with_prefix(FAMILY).collect();
+        completions.sort();
+        assert_eq!(completions, [FAMILY.to_string(), format!("{FAMILY}!")]);
+
+        assert_eq!(trie.iter().len(), 4);
+        assert!(trie.iter().all(|word| words.contains(&word)));
+
+        trie.delete(FAMILY);
+        assert!(!trie.contains(FAMILY));
+        assert!(trie.contains(&format!("{FAMILY}!")));
+    }
+}
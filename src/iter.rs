@@ -0,0 +1,217 @@
+use crate::store::{ChildStore, Children, Hashed};
+use crate::{Symbol, TNode};
+
+/// Lazy depth-first walk over every word stored below a node.
+///
+/// The traversal keeps an explicit stack instead of recursing, so deep
+/// subtrees cannot overflow the call stack.
+pub struct WordsWithPrefix<'a, V = (), K: Symbol = char, S: ChildStore<K> = Hashed> {
+    stack: Vec<(&'a TNode<V, K, S>, usize)>,
+    word: Vec<K>,
+    pending: Option<K::Word>,
+}
+
+impl<'a, V, K: Symbol, S: ChildStore<K>> WordsWithPrefix<'a, V, K, S> {
+    pub(crate) fn new(node: &'a TNode<V, K, S>, prefix: Vec<K>) -> Self {
+        let stack = node.children.values().map(|c| (c, prefix.len())).collect();
+        let pending = node.is_end().then(|| K::to_word(&prefix));
+
+        Self {
+            stack,
+            word: prefix,
+            pending,
+        }
+    }
+
+    pub(crate) fn empty() -> Self {
+        Self {
+            stack: Vec::new(),
+            word: Vec::new(),
+            pending: None,
+        }
+    }
+}
+
+impl<'a, V, K: Symbol, S: ChildStore<K>> Iterator for WordsWithPrefix<'a, V, K, S> {
+    type Item = K::Word;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(word) = self.pending.take() {
+            return Some(word);
+        }
+
+        while let Some((node, len)) = self.stack.pop() {
+            self.word.truncate(len);
+            self.word.push(node.value.clone());
+
+            let len = self.word.len();
+            self.stack.extend(node.children.values().map(|c| (c, len)));
+
+            if node.is_end() {
+                return Some(K::to_word(&self.word));
+            }
+        }
+
+        None
+    }
+}
+
+pub(crate) fn sorted_children<V, K: Symbol, S: ChildStore<K>>(
+    node: &TNode<V, K, S>,
+) -> Vec<&TNode<V, K, S>> {
+    let mut children: Vec<_> = node.children.values().collect();
+    children.sort_unstable_by(|a, b| a.value.cmp(&b.value));
+    children
+}
+
+pub(crate) fn count_words<V, K: Symbol, S: ChildStore<K>>(node: &TNode<V, K, S>) -> usize {
+    let mut count = 0;
+    let mut stack = vec![node];
+
+    while let Some(node) = stack.pop() {
+        count += node.is_end() as usize;
+        stack.extend(node.children.values());
+    }
+
+    count
+}
+
+struct Frame<'a, V, K, S: ChildStore<K>> {
+    node: &'a TNode<V, K, S>,
+    children: Vec<&'a TNode<V, K, S>>,
+    pos: usize,
+}
+
+impl<'a, V, K: Symbol, S: ChildStore<K>> Frame<'a, V, K, S> {
+    fn new(node: &'a TNode<V, K, S>, reverse: bool) -> Self {
+        let mut children = sorted_children(node);
+
+        if reverse {
+            children.reverse();
+        }
+
+        Self {
+            node,
+            children,
+            pos: 0,
+        }
+    }
+}
+
+/// Iterator over every word of a trie and its value, in symbol order of
+/// the words.
+///
+/// Children are only sorted locally as each node is entered, so no word
+/// list is ever collected. Iterating from the back yields the words in
+/// reverse order.
+pub struct Iter<'a, V = (), K = char, S: ChildStore<K> = Hashed> {
+    front: Vec<Frame<'a, V, K, S>>,
+    front_word: Vec<K>,
+    back: Vec<Frame<'a, V, K, S>>,
+    back_word: Vec<K>,
+    remaining: usize,
+}
+
+impl<'a, V, K: Symbol, S: ChildStore<K>> Iter<'a, V, K, S> {
+    pub(crate) fn new(root: &'a TNode<V, K, S>, len: usize) -> Self {
+        Self {
+            front: vec![Frame::new(root, false)],
+            front_word: Vec::new(),
+            back: vec![Frame::new(root, true)],
+            back_word: Vec::new(),
+            remaining: len,
+        }
+    }
+}
+
+impl<'a, V, K: Symbol, S: ChildStore<K>> Iterator for Iter<'a, V, K, S> {
+    type Item = (K::Word, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        // pre-order: a word comes before every word it prefixes
+        loop {
+            let frame = self.front.last_mut()?;
+
+            if let Some(&node) = frame.children.get(frame.pos) {
+                frame.pos += 1;
+                self.front_word.push(node.value.clone());
+                self.front.push(Frame::new(node, false));
+
+                if let Some(data) = &node.data {
+                    self.remaining -= 1;
+                    return Some((K::to_word(&self.front_word), data));
+                }
+            } else {
+                self.front.pop();
+                self.front_word.pop();
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, V, K: Symbol, S: ChildStore<K>> DoubleEndedIterator for Iter<'a, V, K, S> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        // reversed pre-order: a word comes after every word it prefixes
+        loop {
+            let frame = self.back.last_mut()?;
+
+            if let Some(&node) = frame.children.get(frame.pos) {
+                frame.pos += 1;
+                self.back_word.push(node.value.clone());
+                self.back.push(Frame::new(node, true));
+            } else {
+                let node = frame.node;
+                let word = node
+                    .data
+                    .as_ref()
+                    .map(|data| (K::to_word(&self.back_word), data));
+                self.back.pop();
+                self.back_word.pop();
+
+                if let Some(word) = word {
+                    self.remaining -= 1;
+                    return Some(word);
+                }
+            }
+        }
+    }
+}
+
+impl<'a, V, K: Symbol, S: ChildStore<K>> ExactSizeIterator for Iter<'a, V, K, S> {}
+
+/// Iterator over the words of a trie in symbol order.
+pub struct Keys<'a, V = (), K = char, S: ChildStore<K> = Hashed> {
+    pub(crate) inner: Iter<'a, V, K, S>,
+}
+
+impl<'a, V, K: Symbol, S: ChildStore<K>> Iterator for Keys<'a, V, K, S> {
+    type Item = K::Word;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(word, _)| word)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, V, K: Symbol, S: ChildStore<K>> DoubleEndedIterator for Keys<'a, V, K, S> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(word, _)| word)
+    }
+}
+
+impl<'a, V, K: Symbol, S: ChildStore<K>> ExactSizeIterator for Keys<'a, V, K, S> {}
@@ -0,0 +1,100 @@
+#[cfg(feature = "unicode")]
+use crate::Normalization;
+use std::hash::Hash;
+
+/// A single edge label of a trie.
+///
+/// `Word` is the owned form a path of symbols is collected into when a
+/// trie hands words back, e.g. `String` for `char` and `Vec<u8>` for `u8`.
+/// The `Default` value is only used to label the root node.
+pub trait Symbol: Clone + Ord + Hash + Default {
+    type Word;
+
+    fn to_word(symbols: &[Self]) -> Self::Word;
+
+    /// Rewrites a word under a [`Trie`](crate::Trie) normalization policy.
+    /// Only `char` has text to normalize; other symbols pass through.
+    #[cfg(feature = "unicode")]
+    fn normalize(word: Vec<Self>, policy: &Normalization) -> Vec<Self> {
+        let _ = policy;
+        word
+    }
+}
+
+impl Symbol for char {
+    type Word = String;
+
+    fn to_word(symbols: &[Self]) -> Self::Word {
+        symbols.iter().collect()
+    }
+
+    #[cfg(feature = "unicode")]
+    fn normalize(word: Vec<Self>, policy: &Normalization) -> Vec<Self> {
+        policy.apply(&Self::to_word(&word)).chars().collect()
+    }
+}
+
+macro_rules! impl_vec_symbol {
+    ($($ty:ty),*) => {
+        $(
+            impl Symbol for $ty {
+                type Word = Vec<$ty>;
+
+                fn to_word(symbols: &[Self]) -> Self::Word {
+                    symbols.to_vec()
+                }
+            }
+        )*
+    };
+}
+
+impl_vec_symbol!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize, String);
+
+impl<'a> Symbol for &'a str {
+    type Word = Vec<&'a str>;
+
+    fn to_word(symbols: &[Self]) -> Self::Word {
+        symbols.to_vec()
+    }
+}
+
+/// Anything that can be split into the symbols of a trie path.
+pub trait Key<K> {
+    fn symbols(&self) -> impl Iterator<Item = K> + '_;
+}
+
+impl<K, Q: Key<K> + ?Sized> Key<K> for &Q {
+    fn symbols(&self) -> impl Iterator<Item = K> + '_ {
+        (**self).symbols()
+    }
+}
+
+impl Key<char> for str {
+    fn symbols(&self) -> impl Iterator<Item = char> + '_ {
+        self.chars()
+    }
+}
+
+impl Key<char> for String {
+    fn symbols(&self) -> impl Iterator<Item = char> + '_ {
+        self.chars()
+    }
+}
+
+impl<T: Clone> Key<T> for [T] {
+    fn symbols(&self) -> impl Iterator<Item = T> + '_ {
+        self.iter().cloned()
+    }
+}
+
+impl<T: Clone, const N: usize> Key<T> for [T; N] {
+    fn symbols(&self) -> impl Iterator<Item = T> + '_ {
+        self.iter().cloned()
+    }
+}
+
+impl<T: Clone> Key<T> for Vec<T> {
+    fn symbols(&self) -> impl Iterator<Item = T> + '_ {
+        self.iter().cloned()
+    }
+}
@@ -1,168 +1,324 @@
 use fxhash::FxBuildHasher;
+use map::check_symbols;
 use std::collections::HashMap;
+use std::fmt;
+use std::ops::Deref;
+use store::{ChildStore, Children, Hashed, UnsupportedSymbol};
+
+#[cfg(feature = "unicode")]
+use normalize::Normalizer;
+
+mod aho;
+mod art;
+pub mod binary;
+mod concurrent;
+mod cursor;
+mod dawg;
+mod entry;
+pub mod frozen;
+mod fuzzy;
+#[cfg(feature = "unicode")]
+mod grapheme;
+mod iter;
+mod key;
+mod map;
+#[cfg(feature = "unicode")]
+mod normalize;
+mod pattern;
+mod persistent;
+mod radix;
+mod segment;
+#[cfg(feature = "serde")]
+mod serde_impl;
+mod set;
+mod stats;
+pub mod store;
+mod weight;
+
+pub use aho::{AhoCorasick, Match, StreamScanner};
+pub use art::{ArtKeys, ArtTrie};
+pub use binary::ReadError;
+pub use concurrent::ConcurrentTrie;
+pub use cursor::Cursor;
+pub use dawg::{Dawg, DawgError, DawgStats, DawgWords};
+pub use entry::{Entry, OccupiedEntry, VacantEntry};
+pub use frozen::{FrozenTrie, FrozenWords};
+#[cfg(feature = "unicode")]
+pub use grapheme::{graphemes, Grapheme, GraphemeTrie};
+pub use iter::{Iter, Keys, WordsWithPrefix};
+pub use key::{Key, Symbol};
+pub use map::TrieMap;
+#[cfg(feature = "unicode")]
+pub use normalize::{Form, Normalization};
+pub use pattern::{Pattern, PatternError};
+pub use persistent::{Diff, PNode, PersistentTrie};
+pub use radix::{RadixNode, RadixTrie};
+pub use segment::{Segment, SegmentMode, Segmenter};
+#[cfg(feature = "serde")]
+pub use serde_impl::nested;
+pub use stats::TrieStats;
 
 type FxHashMap<K, V> = HashMap<K, V, FxBuildHasher>;
 
-#[derive(Debug)]
-pub struct TNode {
-    pub value: char,
-    pub is_end: bool,
-    pub children: FxHashMap<char, TNode>,
+pub struct TNode<V = (), K = char, S: ChildStore<K> = Hashed> {
+    pub value: K,
+    pub data: Option<V>,
+    /// Ranking weight of the word ending here, `0` for non-terminal nodes.
+    pub weight: u64,
+    /// Highest `weight` of this node and all of its descendants.
+    pub max_weight: u64,
+    pub children: S::Map<TNode<V, K, S>>,
 }
 
-impl TNode {
-    pub fn new(value: char, is_end: bool) -> Self {
+impl<V: fmt::Debug, K: fmt::Debug, S: ChildStore<K>> fmt::Debug for TNode<V, K, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TNode")
+            .field("value", &self.value)
+            .field("data", &self.data)
+            .field("weight", &self.weight)
+            .field("max_weight", &self.max_weight)
+            .field("children", &DebugChildren(self))
+            .finish()
+    }
+}
+
+struct DebugChildren<'a, V, K, S: ChildStore<K>>(&'a TNode<V, K, S>);
+
+impl<V: fmt::Debug, K: fmt::Debug, S: ChildStore<K>> fmt::Debug for DebugChildren<'_, V, K, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.0.children.iter()).finish()
+    }
+}
+
+impl<V: Clone, K: Clone, S: ChildStore<K>> Clone for TNode<V, K, S> {
+    fn clone(&self) -> Self {
+        Self {
+            value: self.value.clone(),
+            data: self.data.clone(),
+            weight: self.weight,
+            max_weight: self.max_weight,
+            children: self.children.clone_with(Self::clone),
+        }
+    }
+}
+
+impl<V, K: Symbol, S: ChildStore<K>> TNode<V, K, S> {
+    pub fn new(value: K, data: Option<V>) -> Self {
         Self {
             value,
-            is_end,
+            data,
+            weight: 0,
+            max_weight: 0,
             children: Default::default(),
         }
     }
 
-    pub fn get_mut(&mut self, key: &char) -> Option<&mut TNode> {
+    pub fn get(&self, key: &K) -> Option<&TNode<V, K, S>> {
+        self.children.get(key)
+    }
+
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut TNode<V, K, S>> {
         self.children.get_mut(key)
     }
 
-    pub fn has(&self, ch: &char) -> bool {
-        self.children.contains_key(&ch)
+    pub fn has(&self, ch: &K) -> bool {
+        self.children.contains_key(ch)
+    }
+
+    pub fn is_end(&self) -> bool {
+        self.data.is_some()
     }
 
     pub fn is_empty(&self) -> bool {
-        !self.is_end && self.children.is_empty()
+        !self.is_end() && self.children.is_empty()
+    }
+
+    pub(crate) fn update_max_weight(&mut self) {
+        let children = self.children.values().map(|c| c.max_weight);
+        self.max_weight = children.fold(self.weight, u64::max);
     }
 }
 
-pub struct Trie {
-    pub root: TNode,
+/// A set of words, stored as a [`TrieMap`] without values.
+///
+/// `S` picks how each node stores its children, see [`store`].
+pub struct Trie<K = char, S: ChildStore<K> = Hashed> {
+    map: TrieMap<(), K, S>,
+    /// Key policy and original spellings, see [`Trie::with_normalization`].
+    #[cfg(feature = "unicode")]
+    normalizer: Option<Box<Normalizer<K>>>,
 }
 
-impl Trie {
-    pub fn new() -> Self {
+/// A trie over raw byte strings.
+pub type ByteTrie = Trie<u8>;
+
+impl<K: fmt::Debug, S: ChildStore<K>> fmt::Debug for Trie<K, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("Trie");
+        debug.field("map", &self.map);
+        #[cfg(feature = "unicode")]
+        debug.field("normalizer", &self.normalizer);
+        debug.finish()
+    }
+}
+
+impl<K: Clone, S: ChildStore<K>> Clone for Trie<K, S> {
+    fn clone(&self) -> Self {
         Self {
-            root: TNode::new('\0', false),
+            map: self.map.clone(),
+            #[cfg(feature = "unicode")]
+            normalizer: self.normalizer.clone(),
         }
     }
+}
 
-    pub fn insert_iter(&mut self, word: &str) {
-        if word.is_empty() {
-            return;
-        }
+impl<K: Symbol, S: ChildStore<K>> Default for Trie<K, S> {
+    fn default() -> Self {
+        Self::from_map(TrieMap::default())
+    }
+}
 
-        let mut node = &mut self.root;
+impl<K, S: ChildStore<K>> Deref for Trie<K, S> {
+    type Target = TrieMap<(), K, S>;
 
-        for current in word.chars() {
-            let next_node = node
-                .children
-                .entry(current)
-                .or_insert_with(|| TNode::new(current, false));
+    fn deref(&self) -> &Self::Target {
+        &self.map
+    }
+}
 
-            node = next_node;
-        }
+impl<'a, K: Symbol, S: ChildStore<K>> IntoIterator for &'a Trie<K, S> {
+    type Item = K::Word;
+    type IntoIter = Keys<'a, (), K, S>;
 
-        node.is_end = true;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
     }
+}
 
-    pub fn insert(&mut self, word: &str) {
-        if word.is_empty() {
-            return;
-        }
+impl<'a, K: Symbol, S: ChildStore<K>, Q: Key<K> + ?Sized + 'a> FromIterator<&'a Q> for Trie<K, S> {
+    fn from_iter<I: IntoIterator<Item = &'a Q>>(iter: I) -> Self {
+        let mut trie = Self::default();
+        trie.extend(iter);
+        trie
+    }
+}
 
-        let node = &mut self.root;
-        let word = word.chars();
-        Trie::insert_rec(node, word);
+impl<'a, K: Symbol, S: ChildStore<K>, Q: Key<K> + ?Sized + 'a> Extend<&'a Q> for Trie<K, S> {
+    fn extend<I: IntoIterator<Item = &'a Q>>(&mut self, iter: I) {
+        for word in iter {
+            self.insert(word);
+        }
     }
+}
 
-    fn insert_rec(node: &mut TNode, mut word: std::str::Chars<'_>) {
-        if let Some(current_ch) = word.next() {
-            let next_node = node
-                .children
-                .entry(current_ch)
-                .or_insert_with(|| TNode::new(current_ch, false));
+impl<K: Symbol> Trie<K> {
+    /// Creates an empty trie with the default [`Hashed`] child tables; use
+    /// [`default`](Default::default) for the other backends.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
 
-            Trie::insert_rec(next_node, word);
-        } else {
-            node.is_end = true;
+impl<K, S: ChildStore<K>> Trie<K, S> {
+    /// Wraps a map built elsewhere, with no normalization policy.
+    pub(crate) fn from_map(map: TrieMap<(), K, S>) -> Self {
+        Self {
+            map,
+            #[cfg(feature = "unicode")]
+            normalizer: None,
         }
     }
 
-    pub fn contains(&mut self, word: &str) -> bool {
-        let mut node = &self.root;
-
-        for current in word.chars() {
-            if let Some(next_node) = node.children.get(&current) {
-                node = next_node;
-            } else {
-                return false;
-            }
+    /// The serialized formats only hold words, so a trie with a
+    /// normalization policy is refused rather than saved without its
+    /// policy and spellings.
+    pub(crate) fn check_serializable(&self) -> Result<(), &'static str> {
+        #[cfg(feature = "unicode")]
+        if self.normalizer.is_some() {
+            return Err("a trie with a normalization policy can't be serialized");
         }
 
-        node.is_end
+        Ok(())
     }
+}
 
-    pub fn delete(&mut self, word: &str) {
-        let node = &mut self.root;
-        let word: Vec<_> = word.chars().collect();
-        Self::delete_rec(node, &word, 0);
+impl<K: Symbol, S: ChildStore<K>> Trie<K, S> {
+    pub fn insert_iter<Q: Key<K> + ?Sized>(&mut self, word: &Q) {
+        self.insert(word);
     }
 
-    pub fn delete_2(&mut self, word: &str) {
-        if word.is_empty() {
+    /// # Panics
+    ///
+    /// If `word` holds a symbol the backend doesn't accept, such as an
+    /// uppercase letter with [`AsciiLower`](store::AsciiLower).
+    /// [`try_insert`](Self::try_insert) returns an error instead.
+    pub fn insert<Q: Key<K> + ?Sized>(&mut self, word: &Q) {
+        #[cfg(feature = "unicode")]
+        if let Some(normalizer) = &mut self.normalizer {
+            let key = normalizer.insert(word);
+            self.map.insert(&key, ());
             return;
         }
 
-        let word = word.chars();
-        Self::deleto_rec(&mut self.root, word);
+        self.map.insert(word, ());
     }
 
-    fn delete_rec(node: &mut TNode, word: &[char], depth: usize) -> bool {
-        if depth > word.len() {
-            return false;
-        }
-
-        if depth == word.len() {
-            if node.is_end {
-                node.is_end = false;
-            }
+    /// Like [`insert`](Self::insert), but first checks every symbol of
+    /// `word` against the backend and leaves the trie untouched if one is
+    /// rejected.
+    pub fn try_insert<Q: Key<K> + ?Sized>(&mut self, word: &Q) -> Result<(), UnsupportedSymbol<K>> {
+        check_symbols::<K, S>(word)?;
+        self.insert(word);
+        Ok(())
+    }
 
-            return node.is_empty();
+    /// # Panics
+    ///
+    /// Like [`insert`](Self::insert).
+    pub fn insert_weighted<Q: Key<K> + ?Sized>(&mut self, word: &Q, weight: u64) {
+        #[cfg(feature = "unicode")]
+        if let Some(normalizer) = &mut self.normalizer {
+            let key = normalizer.insert(word);
+            self.map.insert_weighted(&key, (), weight);
+            return;
         }
 
-        let current = word[depth];
-
-        if let Some(next) = node.get_mut(&current) {
-            if Self::delete_rec(next, word, depth + 1) {
-                node.children.remove(&current);
-            }
+        self.map.insert_weighted(word, (), weight);
+    }
 
-            return node.is_empty();
+    pub fn contains<Q: Key<K> + ?Sized>(&self, word: &Q) -> bool {
+        #[cfg(feature = "unicode")]
+        if let Some(normalizer) = &self.normalizer {
+            return self.map.contains_key(&normalizer.key(word));
         }
 
-        false
+        self.map.contains_key(word)
     }
 
-    fn deleto_rec(node: &mut TNode, mut word: std::str::Chars<'_>) -> bool {
-        if let Some(current_ch) = word.next() {
-            if let Some(next_node) = node.get_mut(&current_ch) {
-                if Self::deleto_rec(next_node, word) {
-                    // post traversal
-                    node.children.remove(&current_ch);
-                }
-
-                return node.is_empty();
-            }
+    pub fn iter(&self) -> Keys<'_, (), K, S> {
+        self.map.keys()
+    }
 
-            return false;
+    pub fn delete<Q: Key<K> + ?Sized>(&mut self, word: &Q) {
+        #[cfg(feature = "unicode")]
+        if let Some(normalizer) = &mut self.normalizer {
+            self.map.remove(&normalizer.remove(word));
+            return;
         }
 
-        if node.is_end {
-            node.is_end = false;
-        }
+        self.map.remove(word);
+    }
 
-        node.is_empty()
+    pub fn delete_2<Q: Key<K> + ?Sized>(&mut self, word: &Q) {
+        self.delete(word);
     }
 
     pub fn clear(&mut self) {
-        self.root.children.clear();
+        self.map.clear();
+        #[cfg(feature = "unicode")]
+        if let Some(normalizer) = &mut self.normalizer {
+            normalizer.clear();
+        }
     }
 }
 
@@ -170,17 +326,17 @@ impl Trie {
 mod tests {
     use super::*;
 
-    const WORDS: [&'static str; 7] = ["coal", "cat", "cin", "catch", "cut", "cit", "camp"];
+    const WORDS: [&str; 7] = ["coal", "cat", "cin", "catch", "cut", "cit", "camp"];
 
     #[test]
     fn integration_test() {
         let mut trie = Trie::new();
-        assert_eq!(trie.root.is_end, false);
+        assert!(!trie.root.is_end());
 
         trie.insert("");
 
-        assert_eq!(trie.contains("\0"), false);
-        assert_eq!(trie.root.is_end, false);
+        assert!(!trie.contains("\0"));
+        assert!(!trie.root.is_end());
 
         for (i, w) in WORDS.iter().enumerate() {
             if i % 2 == 0 {
@@ -194,29 +350,107 @@ mod tests {
             assert!(trie.contains(*w), "should contain \"{}\"", &w);
         }
 
-        assert_eq!(trie.contains("ca"), false, "shouldn't contain \"ca\"");
-        assert_eq!(trie.contains("ci"), false, "shouldn't contain \"ci\"");
-        assert_eq!(trie.contains("co"), false, "shouldn't contain \"co\"");
+        assert!(!trie.contains("ca"), "shouldn't contain \"ca\"");
+        assert!(!trie.contains("ci"), "shouldn't contain \"ci\"");
+        assert!(!trie.contains("co"), "shouldn't contain \"co\"");
 
         // println!("{:#?}", trie.root);
 
         trie.delete("cat");
-        assert_eq!(trie.contains("cat"), false);
-        assert_eq!(trie.contains("catch"), true);
+        assert!(!trie.contains("cat"));
+        assert!(trie.contains("catch"));
 
         trie.delete("coal");
-        assert_eq!(trie.contains("coal"), false);
-        assert_eq!(trie.contains("cut"), true);
-        assert_eq!(trie.contains("catch"), true);
+        assert!(!trie.contains("coal"));
+        assert!(trie.contains("cut"));
+        assert!(trie.contains("catch"));
 
         trie.clear();
 
         for w in WORDS.iter() {
-            assert_eq!(trie.contains(*w), false);
+            assert!(!trie.contains(*w));
         }
         // println!("{:#?}", trie.root);
     }
 
+    #[test]
+    fn test_words_with_prefix() {
+        let mut trie = Trie::new();
+
+        for w in WORDS.iter() {
+            trie.insert(*w);
+        }
+
+        let mut words: Vec<_> = trie.words_with_prefix("ca").collect();
+        words.sort();
+        assert_eq!(words, ["camp", "cat", "catch"]);
+
+        let mut words: Vec<_> = trie.words_with_prefix("").collect();
+        words.sort();
+        let mut expected = WORDS.to_vec();
+        expected.sort();
+        assert_eq!(words, expected);
+
+        assert_eq!(trie.words_with_prefix("cat").count(), 2);
+        assert_eq!(trie.words_with_prefix("x").count(), 0);
+    }
+
+    #[test]
+    fn test_words_with_prefix_deep() {
+        let mut trie = Trie::new();
+        let word = "a".repeat(1_000);
+        trie.insert_iter(&word);
+
+        assert_eq!(trie.words_with_prefix("aaa").next(), Some(word));
+    }
+
+    #[test]
+    fn test_iter_sorted() {
+        let mut trie = Trie::new();
+
+        for w in WORDS.iter() {
+            trie.insert(*w);
+        }
+
+        let mut expected = WORDS.to_vec();
+        expected.sort();
+
+        assert_eq!(trie.iter().len(), WORDS.len());
+        assert_eq!(trie.iter().collect::<Vec<_>>(), expected);
+
+        expected.reverse();
+        assert_eq!(trie.iter().rev().collect::<Vec<_>>(), expected);
+
+        let mut iter = trie.iter();
+        assert_eq!(iter.next().as_deref(), Some("camp"));
+        assert_eq!(iter.next_back().as_deref(), Some("cut"));
+        assert_eq!(iter.next_back().as_deref(), Some("coal"));
+        assert_eq!(iter.next().as_deref(), Some("cat"));
+        assert_eq!(iter.len(), 3);
+        assert_eq!(iter.collect::<Vec<_>>(), ["catch", "cin", "cit"]);
+
+        assert_eq!(Trie::<char>::new().iter().next_back(), None);
+    }
+
+    #[test]
+    fn test_byte_trie() {
+        let words: [&[u8]; 3] = [b"\xfe\xff", b"\xfe", b"ok"];
+        let mut trie: ByteTrie = words.iter().copied().collect();
+
+        assert!(trie.contains(b"\xfe"));
+        assert!(trie.contains(&b"\xfe\xff"[..]));
+        assert!(!trie.contains(b"o"));
+
+        trie.delete(b"\xfe");
+        assert_eq!(
+            trie.iter().collect::<Vec<_>>(),
+            [b"ok".to_vec(), b"\xfe\xff".to_vec()]
+        );
+
+        let tokens: Trie<u32> = [[7, 1, 3], [7, 1, 4]].iter().collect();
+        assert_eq!(tokens.words_with_prefix(&[7, 1]).count(), 2);
+    }
+
     #[test]
     fn test_deleto() {
         let mut trie_me = Trie::new();
@@ -228,7 +462,7 @@ mod tests {
 
         trie_me.delete_2("null");
         trie_me.delete_2("none");
-        assert_eq!(trie_me.contains("null"), false);
-        assert_eq!(trie_me.contains("none"), false);
+        assert!(!trie_me.contains("null"));
+        assert!(!trie_me.contains("none"));
     }
 }
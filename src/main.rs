@@ -0,0 +1,328 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::process::ExitCode;
+use trie_ferris::Trie;
+
+const USAGE: &str = "\
+usage: trie-ferris [--json] <command> [args]
+
+commands:
+    build <words> <out>                   build a trie from a word list, one word per line
+    contains <trie> <word>                check whether the trie contains a word
+    complete <trie> <prefix> [--limit N]  list the words starting with a prefix
+    fuzzy <trie> <query> [--distance N] [--damerau]
+                                          list the words within an edit distance
+    stats <trie>                          print word count, node count and depth
+    dump <trie>                           print every word in sorted order
+
+Use `-` to read the word list from stdin. With --json every result is
+printed as one JSON object per line.";
+
+struct Args {
+    help: bool,
+    json: bool,
+    positional: Vec<String>,
+    limit: Option<usize>,
+    distance: usize,
+    damerau: bool,
+}
+
+impl Args {
+    fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut parsed = Self {
+            help: false,
+            json: false,
+            positional: Vec::new(),
+            limit: None,
+            distance: 1,
+            damerau: false,
+        };
+
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--json" => parsed.json = true,
+                "--damerau" => parsed.damerau = true,
+                "--limit" => parsed.limit = Some(Self::number(&arg, args.next())?),
+                "--distance" => parsed.distance = Self::number(&arg, args.next())?,
+                "-h" | "--help" => parsed.help = true,
+                flag if flag.starts_with("--") => return Err(format!("unknown option {flag}")),
+                _ => parsed.positional.push(arg),
+            }
+        }
+
+        Ok(parsed)
+    }
+
+    fn number(flag: &str, value: Option<String>) -> Result<usize, String> {
+        let value = value.ok_or_else(|| format!("{flag} needs a value"))?;
+        value
+            .parse()
+            .map_err(|_| format!("{flag} expects a number, got \"{value}\""))
+    }
+
+    fn expect<const N: usize>(&self) -> Result<[&str; N], String> {
+        let rest = &self.positional[1..];
+
+        if rest.len() != N {
+            return Err(format!(
+                "{} expects {N} argument(s), got {}\n\n{USAGE}",
+                self.positional[0],
+                rest.len()
+            ));
+        }
+
+        Ok(std::array::from_fn(|i| rest[i].as_str()))
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+
+    for ch in s.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+
+    out.push('"');
+    out
+}
+
+fn load(path: &str) -> Result<Trie, String> {
+    let file = File::open(path).map_err(|err| format!("{path}: {err}"))?;
+    Trie::read_from(BufReader::new(file)).map_err(|err| format!("{path}: {err}"))
+}
+
+fn build(words: &str, out: &str) -> Result<usize, String> {
+    let reader: Box<dyn BufRead> = match words {
+        "-" => Box::new(io::stdin().lock()),
+        path => Box::new(BufReader::new(
+            File::open(path).map_err(|err| format!("{path}: {err}"))?,
+        )),
+    };
+
+    let mut trie = Trie::new();
+
+    for line in reader.lines() {
+        let line = line.map_err(|err| format!("{words}: {err}"))?;
+        trie.insert(line.trim());
+    }
+
+    let file = File::create(out).map_err(|err| format!("{out}: {err}"))?;
+    let mut writer = BufWriter::new(file);
+    trie.write_to(&mut writer)
+        .and_then(|_| writer.flush())
+        .map_err(|err| format!("{out}: {err}"))?;
+
+    Ok(trie.len())
+}
+
+fn run(args: Args, out: &mut impl Write) -> Result<(), String> {
+    let io_err = |err: io::Error| err.to_string();
+
+    if args.help {
+        return writeln!(out, "{USAGE}").map_err(io_err);
+    }
+
+    let Some(command) = args.positional.first() else {
+        return Err(USAGE.to_string());
+    };
+
+    match command.as_str() {
+        "build" => {
+            let [words, path] = args.expect()?;
+            let count = build(words, path)?;
+
+            if args.json {
+                writeln!(out, "{{\"path\":{},\"words\":{count}}}", json_string(path))
+            } else {
+                writeln!(out, "wrote {count} words to {path}")
+            }
+            .map_err(io_err)
+        }
+        "contains" => {
+            let [path, word] = args.expect()?;
+            let found = load(path)?.contains(word);
+
+            if args.json {
+                writeln!(
+                    out,
+                    "{{\"word\":{},\"contains\":{found}}}",
+                    json_string(word)
+                )
+            } else {
+                writeln!(out, "{found}")
+            }
+            .map_err(io_err)
+        }
+        "complete" => {
+            let [path, prefix] = args.expect()?;
+            let trie = load(path)?;
+            let mut words: Vec<_> = trie.words_with_prefix(prefix).collect();
+
+            // keep the `limit` smallest matches, then sort only those
+            if let Some(limit) = args.limit.filter(|&limit| limit < words.len()) {
+                if limit > 0 {
+                    words.select_nth_unstable(limit - 1);
+                }
+                words.truncate(limit);
+            }
+
+            words.sort_unstable();
+
+            for word in words {
+                if args.json {
+                    writeln!(out, "{{\"word\":{}}}", json_string(&word))
+                } else {
+                    writeln!(out, "{word}")
+                }
+                .map_err(io_err)?;
+            }
+
+            Ok(())
+        }
+        "fuzzy" => {
+            let [path, query] = args.expect()?;
+            let trie = load(path)?;
+            let matches = if args.damerau {
+                trie.fuzzy_damerau(query, args.distance)
+            } else {
+                trie.fuzzy(query, args.distance)
+            };
+
+            for (word, distance) in matches {
+                if args.json {
+                    let word = json_string(&word);
+                    writeln!(out, "{{\"word\":{word},\"distance\":{distance}}}")
+                } else {
+                    writeln!(out, "{word}\t{distance}")
+                }
+                .map_err(io_err)?;
+            }
+
+            Ok(())
+        }
+        "stats" => {
+            let [path] = args.expect()?;
+            let trie = load(path)?;
+            let stats = trie.stats();
+            let (words, nodes, depth) = (stats.words, stats.nodes, stats.max_depth);
+
+            if args.json {
+                writeln!(
+                    out,
+                    "{{\"words\":{words},\"nodes\":{nodes},\"depth\":{depth}}}"
+                )
+            } else {
+                writeln!(out, "words\t{words}\nnodes\t{nodes}\ndepth\t{depth}")
+            }
+            .map_err(io_err)
+        }
+        "dump" => {
+            let [path] = args.expect()?;
+
+            for word in load(path)?.iter() {
+                if args.json {
+                    writeln!(out, "{{\"word\":{}}}", json_string(&word))
+                } else {
+                    writeln!(out, "{word}")
+                }
+                .map_err(io_err)?;
+            }
+
+            Ok(())
+        }
+        other => Err(format!("unknown command \"{other}\"\n\n{USAGE}")),
+    }
+}
+
+fn main() -> ExitCode {
+    let result = Args::parse(std::env::args().skip(1)).and_then(|args| {
+        let stdout = io::stdout();
+        let mut out = BufWriter::new(stdout.lock());
+        run(args, &mut out)?;
+        out.flush().map_err(|err| err.to_string())
+    });
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("trie-ferris: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run_str(args: &[&str]) -> Result<String, String> {
+        let args = Args::parse(args.iter().map(|s| s.to_string()))?;
+        let mut out = Vec::new();
+        run(args, &mut out)?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn test_commands() {
+        let dir = std::env::temp_dir().join(format!("trie-ferris-cli-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let words = dir.join("words.txt");
+        let trie = dir.join("words.trie");
+        std::fs::write(&words, "cat\ncatch\n\ncut\n  coal \n\"q\"\n").unwrap();
+
+        let (words, trie) = (words.to_str().unwrap(), trie.to_str().unwrap());
+
+        assert_eq!(
+            run_str(&["build", words, trie]).unwrap(),
+            format!("wrote 5 words to {trie}\n")
+        );
+        assert_eq!(run_str(&["contains", trie, "coal"]).unwrap(), "true\n");
+        assert_eq!(
+            run_str(&["--json", "contains", trie, "ca"]).unwrap(),
+            "{\"word\":\"ca\",\"contains\":false}\n"
+        );
+        assert_eq!(run_str(&["complete", trie, "ca"]).unwrap(), "cat\ncatch\n");
+        assert_eq!(
+            run_str(&["complete", trie, "c", "--limit", "1"]).unwrap(),
+            "cat\n"
+        );
+        assert_eq!(
+            run_str(&["complete", trie, "c", "--limit", "3"]).unwrap(),
+            "cat\ncatch\ncoal\n"
+        );
+        assert_eq!(
+            run_str(&["complete", trie, "c", "--limit", "0"]).unwrap(),
+            ""
+        );
+        assert_eq!(
+            run_str(&["fuzzy", trie, "cot"]).unwrap(),
+            "cat\t1\ncut\t1\n"
+        );
+        assert_eq!(
+            run_str(&["stats", "--json", trie]).unwrap(),
+            "{\"words\":5,\"nodes\":14,\"depth\":5}\n"
+        );
+        assert_eq!(
+            run_str(&["dump", trie, "--json"]).unwrap().lines().next(),
+            Some("{\"word\":\"\\\"q\\\"\"}")
+        );
+
+        assert!(run_str(&["contains", trie]).is_err());
+        assert!(run_str(&["frobnicate"]).is_err());
+        assert!(run_str(&["fuzzy", trie, "cot", "--distance", "x"]).is_err());
+        assert!(run_str(&["dump", words]).is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,306 @@
+use crate::iter::count_words;
+use crate::store::{ChildStore, Children, Hashed, UnsupportedSymbol};
+use crate::{Iter, Key, Keys, Symbol, TNode, WordsWithPrefix};
+use std::fmt;
+
+/// A trie that associates a value with every stored word.
+///
+/// Terminal nodes hold `Some(value)`, every other node holds `None`.
+/// The word count is cached, so editing `root` directly leaves
+/// [`len`](Self::len) stale.
+pub struct TrieMap<V, K = char, S: ChildStore<K> = Hashed> {
+    pub root: TNode<V, K, S>,
+    pub(crate) len: usize,
+}
+
+impl<V: fmt::Debug, K: fmt::Debug, S: ChildStore<K>> fmt::Debug for TrieMap<V, K, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TrieMap")
+            .field("root", &self.root)
+            .field("len", &self.len)
+            .finish()
+    }
+}
+
+impl<V: Clone, K: Clone, S: ChildStore<K>> Clone for TrieMap<V, K, S> {
+    fn clone(&self) -> Self {
+        Self {
+            root: self.root.clone(),
+            len: self.len,
+        }
+    }
+}
+
+impl<V, K: Symbol, S: ChildStore<K>> Default for TrieMap<V, K, S> {
+    fn default() -> Self {
+        Self {
+            root: TNode::new(K::default(), None),
+            len: 0,
+        }
+    }
+}
+
+impl<'a, V, K: Symbol, S: ChildStore<K>> IntoIterator for &'a TrieMap<V, K, S> {
+    type Item = (K::Word, &'a V);
+    type IntoIter = Iter<'a, V, K, S>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Finds the first symbol of `word` that `S` can't store.
+pub(crate) fn check_symbols<K, S: ChildStore<K>>(
+    word: &(impl Key<K> + ?Sized),
+) -> Result<(), UnsupportedSymbol<K>> {
+    match word.symbols().find(|symbol| !S::accepts(symbol)) {
+        Some(symbol) => Err(UnsupportedSymbol(symbol)),
+        None => Ok(()),
+    }
+}
+
+/// Panics if `S` can't store some symbol of `word`, before an insert has
+/// created any node for it.
+pub(crate) fn expect_symbols<K, S: ChildStore<K>>(word: &(impl Key<K> + ?Sized)) {
+    if check_symbols::<K, S>(word).is_err() {
+        panic!("word holds a symbol outside the alphabet of the child store");
+    }
+}
+
+impl<V, K: Symbol> TrieMap<V, K> {
+    /// Creates an empty map with the default [`Hashed`] child tables; use
+    /// [`default`](Default::default) for the other backends.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<V, K: Symbol, S: ChildStore<K>> TrieMap<V, K, S> {
+    /// Wraps a tree built elsewhere, counting its words once.
+    pub(crate) fn with_root(root: TNode<V, K, S>) -> Self {
+        let len = count_words(&root);
+        Self { root, len }
+    }
+
+    /// Number of stored words, in O(1).
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Inserts `value` under `word`, returning the value it replaced.
+    ///
+    /// The empty word is never stored.
+    ///
+    /// # Panics
+    ///
+    /// If `word` holds a symbol the backend doesn't accept, such as an
+    /// uppercase letter with [`AsciiLower`](crate::store::AsciiLower).
+    /// [`try_insert`](Self::try_insert) returns an error instead.
+    pub fn insert<Q: Key<K> + ?Sized>(&mut self, word: &Q, value: V) -> Option<V> {
+        let mut symbols = word.symbols().peekable();
+        symbols.peek()?;
+        expect_symbols::<K, S>(word);
+
+        let mut node = &mut self.root;
+
+        for current in symbols {
+            node = node
+                .children
+                .get_or_insert_with(current.clone(), || TNode::new(current, None));
+        }
+
+        let old = node.data.replace(value);
+        self.len += old.is_none() as usize;
+        old
+    }
+
+    /// Like [`insert`](Self::insert), but first checks every symbol of
+    /// `word` against the backend and leaves the map untouched if one is
+    /// rejected.
+    pub fn try_insert<Q: Key<K> + ?Sized>(
+        &mut self,
+        word: &Q,
+        value: V,
+    ) -> Result<Option<V>, UnsupportedSymbol<K>> {
+        check_symbols::<K, S>(word)?;
+        Ok(self.insert(word, value))
+    }
+
+    pub fn get<Q: Key<K> + ?Sized>(&self, word: &Q) -> Option<&V> {
+        self.find_node(word)?.data.as_ref()
+    }
+
+    pub fn get_mut<Q: Key<K> + ?Sized>(&mut self, word: &Q) -> Option<&mut V> {
+        self.find_node_mut(word)?.data.as_mut()
+    }
+
+    pub fn contains_key<Q: Key<K> + ?Sized>(&self, word: &Q) -> bool {
+        self.find_node(word).is_some_and(|node| node.is_end())
+    }
+
+    /// Removes `word` and returns its value, pruning every node that no
+    /// longer leads to a word.
+    pub fn remove<Q: Key<K> + ?Sized>(&mut self, word: &Q) -> Option<V> {
+        let old = Self::remove_rec(&mut self.root, &mut word.symbols());
+        self.len -= old.is_some() as usize;
+        old
+    }
+
+    fn remove_rec(node: &mut TNode<V, K, S>, word: &mut impl Iterator<Item = K>) -> Option<V> {
+        let Some(current_ch) = word.next() else {
+            node.weight = 0;
+            node.update_max_weight();
+            return node.data.take();
+        };
+
+        let next_node = node.get_mut(&current_ch)?;
+        let old = Self::remove_rec(next_node, word);
+
+        if next_node.is_empty() {
+            // post traversal
+            node.children.remove(&current_ch);
+        }
+
+        if old.is_some() {
+            node.update_max_weight();
+        }
+
+        old
+    }
+
+    pub fn clear(&mut self) {
+        self.root.children.clear();
+        self.root.max_weight = 0;
+        self.len = 0;
+    }
+
+    /// Counts every node, including the root.
+    pub fn node_count(&self) -> usize {
+        let mut count = 0;
+        let mut stack = vec![&self.root];
+
+        while let Some(node) = stack.pop() {
+            count += 1;
+            stack.extend(node.children.values());
+        }
+
+        count
+    }
+
+    pub fn iter(&self) -> Iter<'_, V, K, S> {
+        Iter::new(&self.root, self.len)
+    }
+
+    pub fn keys(&self) -> Keys<'_, V, K, S> {
+        Keys { inner: self.iter() }
+    }
+
+    pub fn words_with_prefix<Q: Key<K> + ?Sized>(
+        &self,
+        prefix: &Q,
+    ) -> WordsWithPrefix<'_, V, K, S> {
+        match self.find_node(prefix) {
+            Some(node) => WordsWithPrefix::new(node, prefix.symbols().collect()),
+            None => WordsWithPrefix::empty(),
+        }
+    }
+
+    pub(crate) fn find_node<Q: Key<K> + ?Sized>(&self, prefix: &Q) -> Option<&TNode<V, K, S>> {
+        let mut node = &self.root;
+
+        for current in prefix.symbols() {
+            node = node.children.get(&current)?;
+        }
+
+        Some(node)
+    }
+
+    pub(crate) fn find_node_mut<Q: Key<K> + ?Sized>(
+        &mut self,
+        prefix: &Q,
+    ) -> Option<&mut TNode<V, K, S>> {
+        let mut node = &mut self.root;
+
+        for current in prefix.symbols() {
+            node = node.children.get_mut(&current)?;
+        }
+
+        Some(node)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_get_remove() {
+        let mut map = TrieMap::new();
+
+        assert_eq!(map.insert("cat", 1), None);
+        assert_eq!(map.insert("catch", 2), None);
+        assert_eq!(map.insert("cat", 3), Some(1));
+        assert_eq!(map.insert("", 4), None);
+
+        assert_eq!(map.get("cat"), Some(&3));
+        assert_eq!(map.get("ca"), None);
+        assert_eq!(map.get(""), None);
+
+        *map.get_mut("catch").unwrap() += 10;
+        assert_eq!(map.get("catch"), Some(&12));
+
+        assert_eq!(map.remove("ca"), None);
+        assert_eq!(map.remove("catch"), Some(12));
+        assert_eq!(map.remove("catch"), None);
+        assert!(map.root.get_mut(&'c').unwrap().has(&'a'));
+        assert!(map.find_node("catc").is_none(), "should prune \"catc\"");
+
+        assert_eq!(map.remove("cat"), Some(3));
+        assert!(map.root.is_empty());
+    }
+
+    #[test]
+    fn test_iter() {
+        let mut map = TrieMap::new();
+
+        for (i, w) in ["cut", "cat", "coal"].iter().enumerate() {
+            map.insert(w, i);
+        }
+
+        let pairs: Vec<_> = map.iter().collect();
+        assert_eq!(
+            pairs,
+            [
+                ("cat".to_string(), &1),
+                ("coal".to_string(), &2),
+                ("cut".to_string(), &0)
+            ]
+        );
+        assert_eq!(map.keys().next_back().as_deref(), Some("cut"));
+    }
+
+    #[test]
+    fn test_generic_symbols() {
+        let mut bytes = TrieMap::<usize, u8>::new();
+        bytes.insert(b"\xff\x00", 1);
+        bytes.insert(&[0xffu8, 0x01][..], 2);
+
+        assert_eq!(bytes.get(b"\xff\x00"), Some(&1));
+        assert_eq!(bytes.get(&vec![0xffu8]), None);
+        assert_eq!(
+            bytes.keys().collect::<Vec<_>>(),
+            [vec![0xffu8, 0x00], vec![0xff, 0x01]]
+        );
+
+        let mut paths = TrieMap::<&str, &str>::new();
+        paths.insert(&["usr", "bin"], "binaries");
+        paths.insert(&["usr", "lib"], "libraries");
+
+        assert_eq!(paths.get(&["usr", "lib"]), Some(&"libraries"));
+        assert_eq!(paths.words_with_prefix(&["usr"]).count(), 2);
+    }
+}
@@ -0,0 +1,579 @@
+use crate::store::ChildStore;
+use crate::{Cursor, Key, Pattern, PatternError, Symbol, Trie, WordsWithPrefix};
+use caseless::default_case_fold_str;
+use std::collections::{BTreeMap, BTreeSet};
+use unicode_normalization::char::{decompose_canonical, is_combining_mark};
+use unicode_normalization::UnicodeNormalization;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Form {
+    /// Keep the composition the word came with.
+    #[default]
+    None,
+    /// Canonical composition, so precomposed and combining spellings of
+    /// the same letter meet.
+    Nfc,
+    /// Compatibility composition, which also folds ligatures, full-width
+    /// forms and the like.
+    Nfkc,
+}
+
+/// How keys are normalized before they reach the trie.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Normalization {
+    pub form: Form,
+    pub case_fold: bool,
+    pub strip_diacritics: bool,
+}
+
+impl Normalization {
+    /// Normalization that leaves keys untouched.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn form(mut self, form: Form) -> Self {
+        self.form = form;
+        self
+    }
+
+    pub fn case_fold(mut self, case_fold: bool) -> Self {
+        self.case_fold = case_fold;
+        self
+    }
+
+    pub fn strip_diacritics(mut self, strip_diacritics: bool) -> Self {
+        self.strip_diacritics = strip_diacritics;
+        self
+    }
+
+    pub fn apply(&self, word: &str) -> String {
+        let mut key: String = match self.form {
+            Form::None => word.to_string(),
+            Form::Nfc => word.nfd().collect(),
+            Form::Nfkc => word.nfkd().collect(),
+        };
+
+        if self.case_fold {
+            key = default_case_fold_str(&key);
+        }
+
+        match self.form {
+            Form::None if self.strip_diacritics => strip_marks(&key),
+            Form::None => key,
+            Form::Nfc | Form::Nfkc => {
+                if self.strip_diacritics {
+                    key.retain(|c| !is_combining_mark(c));
+                }
+
+                if self.form == Form::Nfc {
+                    key.nfc().collect()
+                } else {
+                    key.nfkc().collect()
+                }
+            }
+        }
+    }
+}
+
+/// Drops combining marks without recomposing: a character whose canonical
+/// decomposition carries marks is replaced by the rest of it, every other
+/// character is kept as it came.
+fn strip_marks(word: &str) -> String {
+    let mut stripped = String::with_capacity(word.len());
+
+    for ch in word.chars() {
+        let start = stripped.len();
+        let mut had_marks = false;
+
+        decompose_canonical(ch, |c| match is_combining_mark(c) {
+            true => had_marks = true,
+            false => stripped.push(c),
+        });
+
+        if !had_marks {
+            stripped.truncate(start);
+            stripped.push(ch);
+        }
+    }
+
+    stripped
+}
+
+/// Splits `text` into runs of a char and the combining marks after it,
+/// which normalize without looking at the text around them. Yields each
+/// run with the byte offset it ends at.
+pub(crate) fn units(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    let mut start = 0;
+
+    std::iter::from_fn(move || {
+        let mut chars = text[start..].char_indices();
+        chars.next()?;
+
+        let len = chars
+            .find(|&(_, ch)| !is_combining_mark(ch))
+            .map_or(text.len() - start, |(i, _)| i);
+        let unit = &text[start..start + len];
+        start += len;
+
+        Some((start, unit))
+    })
+}
+
+/// The policy of a normalizing [`Trie`] and the spellings each of its
+/// words was inserted with, keyed by normalized word.
+#[derive(Debug, Clone)]
+pub(crate) struct Normalizer<K> {
+    policy: Normalization,
+    spellings: BTreeMap<Vec<K>, BTreeSet<Vec<K>>>,
+}
+
+impl<K: Symbol> Normalizer<K> {
+    fn new(policy: Normalization) -> Self {
+        Self {
+            policy,
+            spellings: BTreeMap::new(),
+        }
+    }
+
+    pub(crate) fn key<Q: Key<K> + ?Sized>(&self, word: &Q) -> Vec<K> {
+        K::normalize(word.symbols().collect(), &self.policy)
+    }
+
+    /// Records the spelling of `word` and returns the key it is stored
+    /// under.
+    pub(crate) fn insert<Q: Key<K> + ?Sized>(&mut self, word: &Q) -> Vec<K> {
+        let spelling: Vec<K> = word.symbols().collect();
+        let key = K::normalize(spelling.clone(), &self.policy);
+
+        if !key.is_empty() {
+            let spellings = self.spellings.entry(key.clone()).or_default();
+            spellings.insert(spelling);
+        }
+
+        key
+    }
+
+    /// Forgets every spelling of `word` and returns its key.
+    pub(crate) fn remove<Q: Key<K> + ?Sized>(&mut self, word: &Q) -> Vec<K> {
+        let key = self.key(word);
+        self.spellings.remove(&key);
+        key
+    }
+
+    /// The spellings recorded for `key`, or `key` itself if there are none.
+    pub(crate) fn spellings_of(&self, key: &[K]) -> Vec<Vec<K>> {
+        match self.spellings.get(key) {
+            Some(spellings) => spellings.iter().cloned().collect(),
+            None => vec![key.to_vec()],
+        }
+    }
+
+    pub(crate) fn append(&mut self, other: Self) {
+        for (key, spellings) in other.spellings {
+            self.spellings.entry(key).or_default().extend(spellings);
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.spellings.clear();
+    }
+}
+
+impl Trie {
+    /// Creates an empty trie that passes every key through `policy` on
+    /// insert, lookup and delete.
+    ///
+    /// Queries such as [`get`](Trie::get), [`top_k`](Trie::top_k) or
+    /// [`matches`](Trie::matches) normalize their key as well, as do a
+    /// [`Segmenter`](crate::Segmenter) or an
+    /// [`AhoCorasick`](crate::AhoCorasick) built on the trie. The trie
+    /// itself stores normalized words, so iteration and every result hold
+    /// those. The spellings each word was inserted with are kept aside, see
+    /// [`spellings`](Trie::spellings).
+    ///
+    /// The policy and spellings can't be saved: [`write_to`](Trie::write_to),
+    /// [`FrozenTrie::build`](crate::FrozenTrie::build) and serde refuse such
+    /// a trie. Collect its words into a plain trie to save just those.
+    pub fn with_normalization(policy: Normalization) -> Self {
+        let mut trie = Self::new();
+        trie.normalizer = Some(Box::new(Normalizer::new(policy)));
+        trie
+    }
+}
+
+impl<K: Symbol, S: ChildStore<K>> Trie<K, S> {
+    /// The policy keys pass through, if the trie was created with one.
+    pub fn normalization(&self) -> Option<Normalization> {
+        self.normalizer.as_ref().map(|normalizer| normalizer.policy)
+    }
+
+    /// The spellings `word` was inserted with, in sorted order. Empty if
+    /// the word is missing or the trie has no normalization policy.
+    pub fn spellings<Q: Key<K> + ?Sized>(&self, word: &Q) -> Vec<K::Word> {
+        let Some(normalizer) = &self.normalizer else {
+            return Vec::new();
+        };
+
+        let key = normalizer.key(word);
+        let spellings = normalizer.spellings.get(&key).into_iter().flatten();
+        spellings.map(|spelling| K::to_word(spelling)).collect()
+    }
+
+    /// The spellings of every word starting with `prefix`, sorted by
+    /// normalized word and then by spelling.
+    pub fn spellings_with_prefix<Q: Key<K> + ?Sized>(&self, prefix: &Q) -> Vec<K::Word> {
+        let Some(normalizer) = &self.normalizer else {
+            return Vec::new();
+        };
+
+        let prefix = normalizer.key(prefix);
+        normalizer
+            .spellings
+            .range(prefix.clone()..)
+            .take_while(|(key, _)| key.starts_with(&prefix))
+            .flat_map(|(_, spellings)| spellings)
+            .map(|spelling| K::to_word(spelling))
+            .collect()
+    }
+
+    // The queries below shadow the ones `Deref` reaches on the map, so
+    // their keys go through the policy as well.
+
+    pub fn get<Q: Key<K> + ?Sized>(&self, word: &Q) -> Option<&()> {
+        match &self.normalizer {
+            Some(normalizer) => self.map.get(&normalizer.key(word)),
+            None => self.map.get(word),
+        }
+    }
+
+    pub fn contains_key<Q: Key<K> + ?Sized>(&self, word: &Q) -> bool {
+        self.contains(word)
+    }
+
+    pub fn words_with_prefix<Q: Key<K> + ?Sized>(
+        &self,
+        prefix: &Q,
+    ) -> WordsWithPrefix<'_, (), K, S> {
+        match &self.normalizer {
+            Some(normalizer) => self.map.words_with_prefix(&normalizer.key(prefix)),
+            None => self.map.words_with_prefix(prefix),
+        }
+    }
+
+    pub fn weight<Q: Key<K> + ?Sized>(&self, word: &Q) -> Option<u64> {
+        match &self.normalizer {
+            Some(normalizer) => self.map.weight(&normalizer.key(word)),
+            None => self.map.weight(word),
+        }
+    }
+
+    pub fn top_k<Q: Key<K> + ?Sized>(&self, prefix: &Q, k: usize) -> Vec<(K::Word, u64)> {
+        match &self.normalizer {
+            Some(normalizer) => self.map.top_k(&normalizer.key(prefix), k),
+            None => self.map.top_k(prefix, k),
+        }
+    }
+
+    pub fn cursor(&self) -> Cursor<'_, (), K, S> {
+        self.map.cursor().normalizing(self.normalization())
+    }
+
+    /// Drops the spellings of words that left the trie without going
+    /// through [`delete`](Trie::delete).
+    pub(crate) fn prune_spellings(&mut self) {
+        if let Some(normalizer) = &mut self.normalizer {
+            let map = &self.map;
+            normalizer.spellings.retain(|key, _| map.contains_key(key));
+        }
+    }
+}
+
+impl<K: Symbol> Trie<K> {
+    pub fn fuzzy<Q: Key<K> + ?Sized>(
+        &self,
+        query: &Q,
+        max_distance: usize,
+    ) -> Vec<(K::Word, usize)> {
+        match &self.normalizer {
+            Some(normalizer) => self.map.fuzzy(&normalizer.key(query), max_distance),
+            None => self.map.fuzzy(query, max_distance),
+        }
+    }
+
+    pub fn fuzzy_damerau<Q: Key<K> + ?Sized>(
+        &self,
+        query: &Q,
+        max_distance: usize,
+    ) -> Vec<(K::Word, usize)> {
+        match &self.normalizer {
+            Some(normalizer) => self.map.fuzzy_damerau(&normalizer.key(query), max_distance),
+            None => self.map.fuzzy_damerau(query, max_distance),
+        }
+    }
+}
+
+impl Trie {
+    pub fn matches(&self, pattern: &str) -> Result<Vec<String>, PatternError> {
+        Ok(self.matches_pattern(&Pattern::parse(pattern)?))
+    }
+
+    pub fn matches_pattern(&self, pattern: &Pattern) -> Vec<String> {
+        match self.normalization() {
+            Some(policy) => self.map.matches_pattern(&pattern.normalized(&policy)),
+            None => self.map.matches_pattern(pattern),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policy() {
+        let fold = Normalization::new().form(Form::Nfc).case_fold(true);
+        assert_eq!(fold.apply("CAFÉ"), "café");
+        assert_eq!(fold.apply("Cafe\u{301}"), "café");
+        assert_eq!(fold.apply("Straße"), "strasse");
+        assert_eq!(fold.apply("ΌΣΟΣ"), fold.apply("όσος"));
+
+        let strip = fold.strip_diacritics(true);
+        assert_eq!(strip.apply("Crème Brûlée"), "creme brulee");
+
+        let nfkc = Normalization::new().form(Form::Nfkc);
+        assert_eq!(nfkc.apply("ﬁle"), "file");
+        assert_eq!(nfkc.apply("ＡＢＣ"), "ABC");
+
+        assert_eq!(Normalization::new().apply("Cafe\u{301}"), "Cafe\u{301}");
+    }
+
+    #[test]
+    fn test_case_fold() {
+        let fold = Normalization::new().case_fold(true);
+
+        // Armenian ligatures
+        assert_eq!(fold.apply("\u{fb13}"), "\u{574}\u{576}");
+        assert_eq!(fold.apply("\u{fb17}"), "\u{574}\u{56d}");
+        // Greek with iota subscript, both the lower and title case forms
+        assert_eq!(fold.apply("\u{1f80}"), "\u{1f00}\u{3b9}");
+        assert_eq!(fold.apply("\u{1f88}"), "\u{1f00}\u{3b9}");
+        assert_eq!(fold.apply("\u{1ffc}"), "\u{3c9}\u{3b9}");
+        // Cherokee folds to the uppercase letters
+        assert_eq!(fold.apply("\u{ab70}"), "\u{13a0}");
+        assert_eq!(fold.apply("\u{abbf}"), "\u{13ef}");
+        assert_eq!(fold.apply("\u{13f8}"), "\u{13f0}");
+        assert_eq!(fold.apply("\u{13a0}"), "\u{13a0}");
+
+        assert_eq!(fold.apply("ẞ"), "ss");
+        assert_eq!(fold.apply("ΐ"), "\u{3b9}\u{308}\u{301}");
+    }
+
+    #[test]
+    fn test_strip_without_form() {
+        let strip = Normalization::new().strip_diacritics(true);
+
+        assert_eq!(strip.apply("Café"), "Cafe");
+        assert_eq!(strip.apply("Cafe\u{301}"), "Cafe");
+        // nothing is recomposed or decomposed beyond the marks
+        assert_eq!(strip.apply("한국어"), "한국어");
+        assert_eq!(strip.apply("ﬁ"), "ﬁ");
+        assert_eq!(strip.apply("Å"), "A");
+    }
+
+    #[test]
+    fn test_normalized_trie() {
+        let policy = Normalization::new()
+            .form(Form::Nfc)
+            .case_fold(true)
+            .strip_diacritics(true);
+        let mut trie = Trie::with_normalization(policy);
+
+        trie.insert("Café");
+        trie.insert("CAFE");
+        trie.insert("cafeteria");
+        trie.insert("Crème");
+        trie.insert("\u{301}");
+
+        assert_eq!(trie.len(), 3);
+        assert!(trie.contains("cafe"));
+        assert!(trie.contains("CAFÉ"));
+        assert!(trie.contains("cafe\u{301}"));
+        assert!(!trie.contains("caf"));
+
+        assert_eq!(trie.spellings("café"), ["CAFE", "Café"]);
+        assert!(trie.spellings("caf").is_empty());
+        assert_eq!(
+            trie.spellings_with_prefix("CAF"),
+            ["CAFE", "Café", "cafeteria"]
+        );
+        assert_eq!(trie.spellings_with_prefix("cre"), ["Crème"]);
+
+        trie.delete("cAfÉ");
+        assert!(!trie.contains("Café"));
+        assert!(trie.spellings("Café").is_empty());
+        assert!(trie.contains("cafeteria"));
+        assert_eq!(trie.normalization(), Some(policy));
+
+        let other: Trie = ["cafeteria"].into_iter().collect();
+        trie.remove_all_in(&other);
+        assert_eq!(trie.spellings_with_prefix(""), ["Crème"]);
+
+        trie.clear();
+        assert!(trie.spellings_with_prefix("").is_empty());
+        assert_eq!(Trie::<char>::new().normalization(), None);
+    }
+
+    #[test]
+    fn test_extend_across_policies() {
+        let fold = Normalization::new().case_fold(true);
+        let mut trie = Trie::with_normalization(fold);
+        trie.insert_weighted("Cat", 4);
+
+        let mut plain = Trie::new();
+        plain.insert_weighted("CAT", 2);
+        plain.insert_weighted("Dog", 7);
+        trie.extend_from(plain);
+
+        assert_eq!(trie.iter().collect::<Vec<_>>(), ["cat", "dog"]);
+        assert_eq!(trie.spellings("cat"), ["CAT", "Cat"]);
+        assert_eq!(trie.weight("CAT"), Some(4));
+        assert_eq!(trie.weight("dog"), Some(7));
+
+        // the spellings kept under another policy are what gets folded
+        let mut stripping = Trie::with_normalization(Normalization::new().strip_diacritics(true));
+        stripping.insert("Éclair");
+        trie.extend_from(stripping);
+
+        assert!(trie.contains("ÉCLAIR"));
+        assert!(!trie.contains("eclair"));
+        assert_eq!(trie.spellings("éclair"), ["Éclair"]);
+        assert_eq!(trie.len(), 3);
+    }
+
+    #[test]
+    fn test_set_operations_keep_policy() {
+        let fold = Normalization::new().case_fold(true);
+        let mut a = Trie::with_normalization(fold);
+        a.insert("Cat");
+        a.insert("Dog");
+
+        let mut b = Trie::with_normalization(fold);
+        b.insert("CAT");
+        b.insert("Cow");
+
+        let union = a.union(&b);
+        assert_eq!(union.normalization(), Some(fold));
+        assert!(union.contains("COW"));
+        assert_eq!(union.spellings("cat"), ["CAT", "Cat"]);
+        assert_eq!(
+            union.spellings_with_prefix(""),
+            ["CAT", "Cat", "Cow", "Dog"]
+        );
+
+        let common = a.intersection(&b);
+        assert_eq!(common.spellings_with_prefix(""), ["CAT", "Cat"]);
+        assert_eq!(a.difference(&b).spellings_with_prefix(""), ["Dog"]);
+        assert_eq!(
+            a.symmetric_difference(&b).spellings_with_prefix(""),
+            ["Cow", "Dog"]
+        );
+
+        // the other side's spellings only come along under the same policy
+        let plain: Trie = ["dog"].iter().collect();
+        let union = a.union(&plain);
+        assert_eq!(union.spellings("DOG"), ["Dog"]);
+        assert_eq!(plain.union(&a).normalization(), None);
+    }
+
+    fn folding_trie() -> Trie {
+        let policy = Normalization::new()
+            .form(Form::Nfc)
+            .case_fold(true)
+            .strip_diacritics(true);
+        let mut trie = Trie::with_normalization(policy);
+
+        trie.insert_weighted("Café", 3);
+        trie.insert_weighted("cafeteria", 5);
+        trie.insert_weighted("Crème", 1);
+        trie.insert("Straße");
+        trie
+    }
+
+    #[test]
+    fn test_normalized_lookups() {
+        let trie = folding_trie();
+
+        assert_eq!(trie.get("CAFÉ"), Some(&()));
+        assert_eq!(trie.get("cafe\u{301}"), Some(&()));
+        assert_eq!(trie.get("caf"), None);
+        assert!(trie.contains_key("STRASSE"));
+        assert!(trie.contains_key("strasse"));
+        assert_eq!(trie.weight("CAFÉ"), Some(3));
+        assert_eq!(trie.weight("Cafeteria"), Some(5));
+        assert_eq!(trie.weight("Caf"), None);
+    }
+
+    #[test]
+    fn test_normalized_prefix_search() {
+        let trie = folding_trie();
+
+        assert_eq!(
+            trie.words_with_prefix("CAF").collect::<Vec<_>>(),
+            ["cafe", "cafeteria"]
+        );
+        assert_eq!(trie.words_with_prefix("Crè").collect::<Vec<_>>(), ["creme"]);
+        assert_eq!(
+            trie.top_k("Ca", 2),
+            [("cafeteria".to_string(), 5), ("cafe".to_string(), 3)]
+        );
+        assert_eq!(trie.top_k("CRÈ", 1), [("creme".to_string(), 1)]);
+    }
+
+    #[test]
+    fn test_normalized_fuzzy() {
+        let trie = folding_trie();
+
+        assert_eq!(trie.fuzzy("CAFÉ", 0), [("cafe".to_string(), 0)]);
+        assert_eq!(trie.fuzzy("Crème", 1), [("creme".to_string(), 0)]);
+        assert_eq!(trie.fuzzy_damerau("ACFÉ", 1), [("cafe".to_string(), 1)]);
+    }
+
+    #[test]
+    fn test_normalized_matches() {
+        let trie = folding_trie();
+
+        assert_eq!(trie.matches("CAF?").unwrap(), ["cafe"]);
+        assert_eq!(trie.matches("Cre\u{300}*").unwrap(), ["creme"]);
+        assert_eq!(trie.matches("[A-C]*É").unwrap(), ["cafe", "creme"]);
+        assert_eq!(trie.matches("*SS*").unwrap(), ["strasse"]);
+        assert_eq!(
+            trie.matches_pattern(&Pattern::parse("STRAẞE").unwrap()),
+            ["strasse"]
+        );
+    }
+
+    #[test]
+    fn test_normalized_cursor() {
+        let trie = folding_trie();
+        let mut cursor = trie.cursor();
+
+        assert!(cursor.can_advance(&'C'));
+        assert!("CAFÉ".chars().all(|ch| cursor.advance(ch)));
+        assert!(cursor.is_word());
+        assert_eq!(cursor.depth(), 4);
+
+        // a mark on its own is stripped, and ß takes two steps
+        assert!(cursor.advance('\u{301}'));
+        assert_eq!(cursor.depth(), 4);
+
+        cursor.reset();
+        assert!("STRAß".chars().all(|ch| cursor.advance(ch)));
+        assert_eq!(cursor.depth(), 6);
+        assert!(cursor.advance('E'));
+        assert!(cursor.is_word());
+
+        assert!(!cursor.advance('x'));
+        assert!(!cursor.advance('\u{301}'));
+    }
+}
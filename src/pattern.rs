@@ -0,0 +1,307 @@
+use crate::{TNode, TrieMap};
+use std::collections::HashSet;
+use std::fmt;
+
+#[cfg(feature = "unicode")]
+use crate::Normalization;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatternError {
+    /// A `[` without its closing `]`.
+    UnclosedClass(usize),
+    /// A `[]` or `[!]` that can never match.
+    EmptyClass(usize),
+    /// A range such as `z-a` whose start is after its end.
+    InvalidRange(usize),
+    /// A trailing `\` with nothing to escape.
+    DanglingEscape,
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnclosedClass(pos) => write!(f, "unclosed character class at {pos}"),
+            Self::EmptyClass(pos) => write!(f, "empty character class at {pos}"),
+            Self::InvalidRange(pos) => write!(f, "invalid range in character class at {pos}"),
+            Self::DanglingEscape => write!(f, "pattern ends with an escape"),
+        }
+    }
+}
+
+impl std::error::Error for PatternError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Char(char),
+    Any,
+    Star,
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+}
+
+impl Token {
+    fn accepts(&self, ch: char) -> bool {
+        match self {
+            Self::Char(c) => *c == ch,
+            Self::Any | Self::Star => true,
+            Self::Class { negated, ranges } => {
+                ranges.iter().any(|&(lo, hi)| lo <= ch && ch <= hi) != *negated
+            }
+        }
+    }
+}
+
+/// A parsed wildcard pattern.
+///
+/// `?` matches any single char, `*` any run of chars (including none) and
+/// `[...]` one char of a set, e.g. `[aeiou]`, `[a-z]` or the negated
+/// `[!0-9]`. A `\` matches the following char literally.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern {
+    tokens: Vec<Token>,
+}
+
+impl Pattern {
+    pub fn parse(pattern: &str) -> Result<Self, PatternError> {
+        let chars: Vec<char> = pattern.chars().collect();
+        let mut tokens = Vec::new();
+        let mut i = 0;
+
+        while i < chars.len() {
+            let token = match chars[i] {
+                '?' => Token::Any,
+                // consecutive stars match the same runs as a single one
+                '*' if tokens.last() == Some(&Token::Star) => {
+                    i += 1;
+                    continue;
+                }
+                '*' => Token::Star,
+                '\\' => {
+                    i += 1;
+                    Token::Char(*chars.get(i).ok_or(PatternError::DanglingEscape)?)
+                }
+                '[' => {
+                    let (token, end) = Self::parse_class(&chars, i)?;
+                    i = end;
+                    token
+                }
+                ch => Token::Char(ch),
+            };
+
+            tokens.push(token);
+            i += 1;
+        }
+
+        Ok(Self { tokens })
+    }
+
+    /// Parses the class opening at `start`, returning it and the index of
+    /// its closing `]`.
+    fn parse_class(chars: &[char], start: usize) -> Result<(Token, usize), PatternError> {
+        let mut i = start + 1;
+        let negated = matches!(chars.get(i), Some('!' | '^'));
+
+        if negated {
+            i += 1;
+        }
+
+        let mut ranges = Vec::new();
+
+        loop {
+            let lo = match chars.get(i) {
+                None => return Err(PatternError::UnclosedClass(start)),
+                Some(']') => break,
+                Some('\\') => {
+                    i += 1;
+                    *chars.get(i).ok_or(PatternError::UnclosedClass(start))?
+                }
+                Some(&ch) => ch,
+            };
+
+            let hi = match (chars.get(i + 1), chars.get(i + 2)) {
+                (Some('-'), Some(&hi)) if hi != ']' => {
+                    i += 2;
+                    hi
+                }
+                _ => lo,
+            };
+
+            if lo > hi {
+                return Err(PatternError::InvalidRange(start));
+            }
+
+            ranges.push((lo, hi));
+            i += 1;
+        }
+
+        if ranges.is_empty() {
+            return Err(PatternError::EmptyClass(start));
+        }
+
+        Ok((Token::Class { negated, ranges }, i))
+    }
+
+    /// Rewrites the literal chars and class bounds under `policy`, so the
+    /// pattern matches the words of a normalizing trie. A run of literals
+    /// is normalized as one piece, which lets combining marks compose.
+    #[cfg(feature = "unicode")]
+    pub(crate) fn normalized(&self, policy: &Normalization) -> Self {
+        let mut tokens = Vec::with_capacity(self.tokens.len());
+        let mut literals = String::new();
+
+        for token in &self.tokens {
+            let token = match token {
+                Token::Char(ch) => {
+                    literals.push(*ch);
+                    continue;
+                }
+                Token::Class { negated, ranges } => Token::Class {
+                    negated: *negated,
+                    ranges: ranges
+                        .iter()
+                        .map(|&range| normalized_range(range, policy))
+                        .collect(),
+                },
+                token => token.clone(),
+            };
+
+            tokens.extend(policy.apply(&literals).chars().map(Token::Char));
+            literals.clear();
+            tokens.push(token);
+        }
+
+        tokens.extend(policy.apply(&literals).chars().map(Token::Char));
+        Self { tokens }
+    }
+}
+
+/// Normalizes both bounds of a class range, keeping the range as it was if
+/// a bound doesn't stay a single char or the bounds would swap.
+#[cfg(feature = "unicode")]
+fn normalized_range((lo, hi): (char, char), policy: &Normalization) -> (char, char) {
+    let single = |ch: char| {
+        let normalized = policy.apply(ch.encode_utf8(&mut [0; 4]));
+        let mut chars = normalized.chars();
+        chars.next().filter(|_| chars.next().is_none())
+    };
+
+    match (single(lo), single(hi)) {
+        (Some(new_lo), Some(new_hi)) if new_lo <= new_hi => (new_lo, new_hi),
+        _ => (lo, hi),
+    }
+}
+
+impl<V> TrieMap<V, char> {
+    /// Returns every word matching the wildcard `pattern`, in sorted order.
+    ///
+    /// See [`Pattern`] for the syntax.
+    pub fn matches(&self, pattern: &str) -> Result<Vec<String>, PatternError> {
+        Ok(self.matches_pattern(&Pattern::parse(pattern)?))
+    }
+
+    pub fn matches_pattern(&self, pattern: &Pattern) -> Vec<String> {
+        let tokens = &pattern.tokens;
+        let mut matches = Vec::new();
+        let mut word = String::new();
+        let mut visited = HashSet::new();
+        let mut stack: Vec<(&TNode<V>, usize, usize)> = vec![(&self.root, 0, 0)];
+
+        while let Some((node, pos, len)) = stack.pop() {
+            // a star can reach the same state along several paths
+            if !visited.insert((node as *const TNode<V>, pos)) {
+                continue;
+            }
+
+            // `len` counts the node's own char, which ends the word so far
+            if len > 0 {
+                word.truncate(len - node.value.len_utf8());
+                word.push(node.value);
+            }
+
+            let Some(token) = tokens.get(pos) else {
+                if node.is_end() {
+                    matches.push(word.clone());
+                }
+                continue;
+            };
+
+            match token {
+                Token::Char(ch) => {
+                    if let Some(next) = node.children.get(ch) {
+                        stack.push((next, pos + 1, len + ch.len_utf8()));
+                    }
+                }
+                Token::Star => {
+                    stack.push((node, pos + 1, len));
+                    stack.extend(
+                        node.children
+                            .values()
+                            .map(|next| (next, pos, len + next.value.len_utf8())),
+                    );
+                }
+                token => {
+                    stack.extend(
+                        node.children
+                            .values()
+                            .filter(|next| token.accepts(next.value))
+                            .map(|next| (next, pos + 1, len + next.value.len_utf8())),
+                    );
+                }
+            }
+        }
+
+        matches.sort_unstable();
+        matches
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Trie;
+
+    const WORDS: [&str; 9] = [
+        "cat", "cot", "cut", "coat", "ca", "camp", "catch", "cart", "bat",
+    ];
+
+    fn trie() -> Trie {
+        WORDS.iter().copied().collect()
+    }
+
+    #[test]
+    fn test_matches() {
+        let trie = trie();
+
+        assert_eq!(trie.matches("c?t").unwrap(), ["cat", "cot", "cut"]);
+        assert_eq!(
+            trie.matches("ca*").unwrap(),
+            ["ca", "camp", "cart", "cat", "catch"]
+        );
+        assert_eq!(trie.matches("c[aeiou]t").unwrap(), ["cat", "cot", "cut"]);
+        assert_eq!(trie.matches("c[!a]t").unwrap(), ["cot", "cut"]);
+        assert_eq!(trie.matches("[a-b]*").unwrap(), ["bat"]);
+        assert_eq!(
+            trie.matches("*t").unwrap(),
+            ["bat", "cart", "cat", "coat", "cot", "cut"]
+        );
+        assert_eq!(
+            trie.matches("*a*t*").unwrap(),
+            ["bat", "cart", "cat", "catch", "coat"]
+        );
+        assert_eq!(trie.matches("cat").unwrap(), ["cat"]);
+        assert_eq!(trie.matches("c\\?t").unwrap(), Vec::<String>::new());
+        assert_eq!(trie.matches("").unwrap(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_pattern_errors() {
+        assert_eq!(Pattern::parse("c[at"), Err(PatternError::UnclosedClass(1)));
+        assert_eq!(Pattern::parse("c[]"), Err(PatternError::EmptyClass(1)));
+        assert_eq!(Pattern::parse("[z-a]"), Err(PatternError::InvalidRange(0)));
+        assert_eq!(Pattern::parse("ca\\"), Err(PatternError::DanglingEscape));
+        assert_eq!(Pattern::parse("a**b"), Pattern::parse("a*b"));
+        assert!(Pattern::parse("[a-]").is_ok());
+    }
+}
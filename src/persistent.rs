@@ -0,0 +1,452 @@
+use crate::{Key, Symbol, Trie};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+#[derive(Debug, Clone)]
+pub struct PNode<K = char> {
+    pub value: K,
+    pub is_end: bool,
+    pub children: BTreeMap<K, Arc<PNode<K>>>,
+}
+
+impl<K: Symbol> PNode<K> {
+    fn new(value: K, is_end: bool) -> Self {
+        Self {
+            value,
+            is_end,
+            children: BTreeMap::new(),
+        }
+    }
+
+    /// Builds the fresh path `first`, `rest..` ending in a terminal node.
+    fn chain(first: &K, rest: &[K]) -> Self {
+        let mut symbols = rest.iter().rev().chain([first]);
+        let last = symbols.next().unwrap_or(first);
+        let mut node = Self::new(last.clone(), true);
+
+        for symbol in symbols {
+            let mut parent = Self::new(symbol.clone(), false);
+            parent.children.insert(node.value.clone(), Arc::new(node));
+            node = parent;
+        }
+
+        node
+    }
+}
+
+impl<K> Drop for PNode<K> {
+    fn drop(&mut self) {
+        // unlink the children this node owns alone before they drop, so a
+        // long word doesn't recurse once per symbol
+        let mut stack: Vec<_> = std::mem::take(&mut self.children).into_values().collect();
+
+        while let Some(node) = stack.pop() {
+            if let Some(mut node) = Arc::into_inner(node) {
+                stack.extend(std::mem::take(&mut node.children).into_values());
+            }
+        }
+    }
+}
+
+/// Words added and removed between two versions of a [`PersistentTrie`].
+#[derive(Debug, PartialEq, Eq)]
+pub struct Diff<W> {
+    pub added: Vec<W>,
+    pub removed: Vec<W>,
+}
+
+/// An immutable trie whose updates return a new version.
+///
+/// Versions share every untouched subtree through `Arc`, an update only
+/// copies the nodes along the path of the changed word. Old versions stay
+/// valid and can be read from other threads while newer ones are built.
+#[derive(Debug, Clone)]
+pub struct PersistentTrie<K = char> {
+    root: Arc<PNode<K>>,
+    len: usize,
+}
+
+impl<K: Symbol> Default for PersistentTrie<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Symbol> PersistentTrie<K> {
+    pub fn new() -> Self {
+        Self {
+            root: Arc::new(PNode::new(K::default(), false)),
+            len: 0,
+        }
+    }
+
+    pub fn root(&self) -> &PNode<K> {
+        &self.root
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn contains<Q: Key<K> + ?Sized>(&self, word: &Q) -> bool {
+        let mut node = &self.root;
+
+        for current in word.symbols() {
+            match node.children.get(&current) {
+                Some(next) => node = next,
+                None => return false,
+            }
+        }
+
+        node.is_end
+    }
+
+    /// Returns a version that also contains `word`.
+    pub fn insert<Q: Key<K> + ?Sized>(&self, word: &Q) -> Self {
+        let word: Vec<K> = word.symbols().collect();
+
+        if word.is_empty() {
+            return self.clone();
+        }
+
+        match Self::insert_path(&self.root, &word) {
+            Some(root) => Self {
+                root,
+                len: self.len + 1,
+            },
+            None => self.clone(),
+        }
+    }
+
+    /// Copies the spine down to the new word, `None` if it's already there.
+    fn insert_path(root: &Arc<PNode<K>>, word: &[K]) -> Option<Arc<PNode<K>>> {
+        let mut spine = vec![root];
+
+        for current in word {
+            match spine[spine.len() - 1].children.get(current) {
+                Some(child) => spine.push(child),
+                None => break,
+            }
+        }
+
+        let found = spine.len() - 1;
+        let mut child = match word.get(found) {
+            Some(first) => Arc::new(PNode::chain(first, &word[found + 1..])),
+            None => {
+                let node = spine.pop()?;
+
+                if node.is_end {
+                    return None;
+                }
+
+                let mut node = PNode::clone(node);
+                node.is_end = true;
+                Arc::new(node)
+            }
+        };
+
+        // copy the spine bottom-up, each copy taking the new child
+        for (node, current) in spine.into_iter().zip(word).rev() {
+            let mut node = PNode::clone(node);
+            node.children.insert(current.clone(), child);
+            child = Arc::new(node);
+        }
+
+        Some(child)
+    }
+
+    /// Returns a version without `word`, pruning the nodes that no longer
+    /// lead to a word.
+    pub fn delete<Q: Key<K> + ?Sized>(&self, word: &Q) -> Self {
+        let word: Vec<K> = word.symbols().collect();
+
+        match Self::delete_path(&self.root, &word) {
+            Some(root) => Self {
+                root: root.unwrap_or_else(|| Arc::new(PNode::new(K::default(), false))),
+                len: self.len - 1,
+            },
+            None => self.clone(),
+        }
+    }
+
+    /// `None` if the word isn't there, `Some(None)` if the root is left
+    /// without words, `Some(Some(copy))` otherwise.
+    fn delete_path(root: &Arc<PNode<K>>, word: &[K]) -> Option<Option<Arc<PNode<K>>>> {
+        let mut spine = vec![root];
+
+        for current in word {
+            spine.push(spine[spine.len() - 1].children.get(current)?);
+        }
+
+        let node = spine.pop()?;
+
+        if !node.is_end {
+            return None;
+        }
+
+        let mut copy = PNode::clone(node);
+        copy.is_end = false;
+        let mut child = Some(copy);
+
+        // copy the spine bottom-up, pruning the nodes left without words
+        for (node, current) in spine.into_iter().zip(word).rev() {
+            let mut copy = PNode::clone(node);
+
+            match child.filter(|child| child.is_end || !child.children.is_empty()) {
+                Some(child) => copy.children.insert(current.clone(), Arc::new(child)),
+                None => copy.children.remove(current),
+            };
+
+            child = Some(copy);
+        }
+
+        Some(child.filter(|root| !root.children.is_empty()).map(Arc::new))
+    }
+
+    /// Returns every word in sorted order.
+    pub fn words(&self) -> Vec<K::Word> {
+        let mut words = Vec::new();
+        collect(&self.root, &mut Vec::new(), &mut words);
+        words
+    }
+
+    /// Lists the words `other` has and `self` lacks as added, and the other
+    /// way round as removed.
+    ///
+    /// Subtrees shared by both versions are skipped without being visited.
+    pub fn diff(&self, other: &Self) -> Diff<K::Word> {
+        let mut diff = Diff {
+            added: Vec::new(),
+            removed: Vec::new(),
+        };
+
+        diff_nodes(&self.root, &other.root, &mut diff);
+        diff
+    }
+}
+
+/// Pushes every word below `node`, whose own path is `path`.
+fn collect<K: Symbol>(node: &PNode<K>, path: &mut Vec<K>, words: &mut Vec<K::Word>) {
+    let base = path.len();
+    let mut stack = vec![(node, base)];
+
+    while let Some((node, len)) = stack.pop() {
+        if len > base {
+            path.truncate(len - 1);
+            path.push(node.value.clone());
+        }
+
+        if node.is_end {
+            words.push(K::to_word(path));
+        }
+
+        // reversed, so the smallest key is popped first
+        let children = node.children.values().rev();
+        stack.extend(children.map(|child| (&**child, len + 1)));
+    }
+
+    path.truncate(base);
+}
+
+/// A subtree still to compare, see [`diff_nodes`].
+enum Pending<'a, K> {
+    Both(&'a Arc<PNode<K>>, &'a Arc<PNode<K>>),
+    Removed(&'a PNode<K>),
+    Added(&'a PNode<K>),
+}
+
+fn diff_nodes<K: Symbol>(old: &Arc<PNode<K>>, new: &Arc<PNode<K>>, diff: &mut Diff<K::Word>) {
+    let mut path = Vec::new();
+    let mut stack = vec![(Pending::Both(old, new), 0)];
+    let mut next = Vec::new();
+
+    while let Some((pending, len)) = stack.pop() {
+        let (old, new) = match pending {
+            Pending::Both(old, new) => (old, new),
+            Pending::Removed(node) | Pending::Added(node) => {
+                path.truncate(len - 1);
+                path.push(node.value.clone());
+
+                let words = match pending {
+                    Pending::Removed(_) => &mut diff.removed,
+                    _ => &mut diff.added,
+                };
+                collect(node, &mut path, words);
+                continue;
+            }
+        };
+
+        if len > 0 {
+            path.truncate(len - 1);
+            path.push(new.value.clone());
+        }
+
+        if Arc::ptr_eq(old, new) {
+            continue;
+        }
+
+        match (old.is_end, new.is_end) {
+            (false, true) => diff.added.push(K::to_word(&path)),
+            (true, false) => diff.removed.push(K::to_word(&path)),
+            _ => {}
+        }
+
+        let mut old_children = old.children.iter().peekable();
+        let mut new_children = new.children.iter().peekable();
+
+        // merge both sorted child lists
+        loop {
+            let order = match (old_children.peek(), new_children.peek()) {
+                (None, None) => break,
+                (Some(_), None) => std::cmp::Ordering::Less,
+                (None, Some(_)) => std::cmp::Ordering::Greater,
+                (Some((a, _)), Some((b, _))) => a.cmp(b),
+            };
+
+            next.push(match order {
+                std::cmp::Ordering::Less => Pending::Removed(old_children.next().unwrap().1),
+                std::cmp::Ordering::Greater => Pending::Added(new_children.next().unwrap().1),
+                std::cmp::Ordering::Equal => {
+                    let (_, old_child) = old_children.next().unwrap();
+                    let (_, new_child) = new_children.next().unwrap();
+                    Pending::Both(old_child, new_child)
+                }
+            });
+        }
+
+        // reversed, so the smallest key is popped first
+        stack.extend(next.drain(..).rev().map(|pending| (pending, len + 1)));
+    }
+}
+
+impl<K: Symbol> From<&Trie<K>> for PersistentTrie<K> {
+    fn from(trie: &Trie<K>) -> Self {
+        // post-order, a node is built once its children are on `built`
+        let mut stack = vec![(&trie.root, false)];
+        let mut built: Vec<PNode<K>> = Vec::new();
+
+        while let Some((node, expanded)) = stack.pop() {
+            if !expanded {
+                stack.push((node, true));
+                stack.extend(node.children.values().map(|child| (child, false)));
+                continue;
+            }
+
+            let mut pnode = PNode::new(node.value.clone(), node.is_end());
+
+            for child in built.split_off(built.len() - node.children.len()) {
+                pnode.children.insert(child.value.clone(), Arc::new(child));
+            }
+
+            built.push(pnode);
+        }
+
+        Self {
+            root: Arc::new(built.pop().expect("the root is built last")),
+            len: trie.len(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn test_versions() {
+        is_send_sync::<PersistentTrie>();
+
+        let v0 = PersistentTrie::new();
+        let v1 = v0.insert("cat").insert("catch").insert("cut");
+        let v2 = v1.insert("coal").delete("cat");
+        let v3 = v2.delete("catch");
+
+        assert!(v0.is_empty());
+        assert_eq!(v1.words(), ["cat", "catch", "cut"]);
+        assert_eq!(v2.words(), ["catch", "coal", "cut"]);
+        assert_eq!(v3.words(), ["coal", "cut"]);
+        assert_eq!(v3.len(), 2);
+        assert!(!v3.root().children[&'c'].children.contains_key(&'a'));
+
+        // unchanged operations keep the same root
+        assert!(Arc::ptr_eq(&v3.root, &v3.insert("cut").root));
+        assert!(Arc::ptr_eq(&v3.root, &v3.delete("ca").root));
+        assert_eq!(v3.insert("").len(), 2);
+
+        assert!(v1.contains("cat"));
+        assert!(!v1.contains("ca"));
+    }
+
+    #[test]
+    fn test_structural_sharing() {
+        let v1 = PersistentTrie::new().insert("apple").insert("banana");
+        let v2 = v1.insert("bandana");
+
+        let (a1, a2) = (&v1.root.children[&'a'], &v2.root.children[&'a']);
+        assert!(Arc::ptr_eq(a1, a2), "untouched subtree should be shared");
+        assert!(!Arc::ptr_eq(
+            &v1.root.children[&'b'],
+            &v2.root.children[&'b']
+        ));
+
+        let nana = |v: &PersistentTrie| -> Arc<PNode> {
+            let mut node = &v.root;
+            for ch in "bana".chars() {
+                node = &node.children[&ch];
+            }
+            Arc::clone(node)
+        };
+        assert!(Arc::ptr_eq(&nana(&v1), &nana(&v2)));
+    }
+
+    #[test]
+    fn test_diff() {
+        let trie: Trie = ["cat", "catch", "cut"].iter().collect();
+        let v1 = PersistentTrie::from(&trie);
+        let v2 = v1.delete("cat").insert("coal").insert("cot").delete("cut");
+
+        assert_eq!(
+            v1.diff(&v2),
+            Diff {
+                added: vec!["coal".to_string(), "cot".to_string()],
+                removed: vec!["cat".to_string(), "cut".to_string()],
+            }
+        );
+        assert_eq!(v2.diff(&v1).added, ["cat", "cut"]);
+        assert_eq!(v1.diff(&v1).added.len(), 0);
+        assert_eq!(PersistentTrie::from(&trie).words(), ["cat", "catch", "cut"]);
+        assert_eq!(PersistentTrie::from(&trie).len(), 3);
+    }
+
+    #[test]
+    fn test_deep_keys() {
+        let deep = "ab".repeat(100_000);
+        let half = &deep[..100_000];
+
+        let v1 = PersistentTrie::new().insert(deep.as_str()).insert("b");
+        let v2 = v1.insert(half).delete(deep.as_str());
+
+        assert!(v1.contains(deep.as_str()));
+        assert!(!v2.contains(deep.as_str()));
+        assert!(v2.contains(half));
+        assert_eq!(v1.len(), 2);
+        assert_eq!(v2.words(), [half, "b"]);
+        assert_eq!(
+            v1.diff(&v2),
+            Diff {
+                added: vec![half.to_string()],
+                removed: vec![deep.clone()],
+            }
+        );
+
+        let v3 = v2.delete(half);
+        assert_eq!(v3.words(), ["b"]);
+        assert!(!v3.root().children.contains_key(&'a'));
+    }
+}
@@ -0,0 +1,282 @@
+use crate::{FxHashMap, TNode, Trie};
+
+/// A node of a [`RadixTrie`], reached through the edge fragment `label`.
+#[derive(Debug, Default)]
+pub struct RadixNode {
+    pub label: String,
+    pub is_end: bool,
+    /// Child nodes keyed by the first char of their label.
+    pub children: FxHashMap<char, RadixNode>,
+}
+
+impl RadixNode {
+    fn new(label: String, is_end: bool) -> Self {
+        Self {
+            label,
+            is_end,
+            children: Default::default(),
+        }
+    }
+
+    pub fn has(&self, ch: &char) -> bool {
+        self.children.contains_key(ch)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        !self.is_end && self.children.is_empty()
+    }
+
+    /// Folds a lone child into this node, unless this node ends a word.
+    fn merge_child(&mut self) {
+        if self.is_end || self.children.len() != 1 {
+            return;
+        }
+
+        let Some((_, child)) = self.children.drain().next() else {
+            return;
+        };
+
+        self.label.push_str(&child.label);
+        self.is_end = child.is_end;
+        self.children = child.children;
+    }
+}
+
+/// A compressed trie that stores string fragments on its edges, so chains
+/// of single-child nodes collapse into one node.
+#[derive(Debug, Default)]
+pub struct RadixTrie {
+    pub root: RadixNode,
+}
+
+impl RadixTrie {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, word: &str) {
+        if word.is_empty() {
+            return;
+        }
+
+        let mut node = &mut self.root;
+        let mut rest = word;
+
+        while let Some(first) = rest.chars().next() {
+            if !node.has(&first) {
+                let leaf = RadixNode::new(rest.to_string(), true);
+                node.children.insert(first, leaf);
+                return;
+            }
+
+            let child = node.children.get_mut(&first).unwrap();
+            let common = common_prefix_len(&child.label, rest);
+
+            if common < child.label.len() {
+                // split the edge, the old child keeps the unmatched suffix
+                let suffix = child.label.split_off(common);
+                let mut lower = RadixNode::new(suffix, child.is_end);
+                lower.children = std::mem::take(&mut child.children);
+
+                child.is_end = false;
+                child.children.insert(first_char(&lower.label), lower);
+            }
+
+            node = child;
+            rest = &rest[common..];
+        }
+
+        node.is_end = true;
+    }
+
+    pub fn contains(&self, word: &str) -> bool {
+        let mut node = &self.root;
+        let mut rest = word;
+
+        while let Some(first) = rest.chars().next() {
+            let Some(child) = node.children.get(&first) else {
+                return false;
+            };
+
+            let Some(next) = rest.strip_prefix(child.label.as_str()) else {
+                return false;
+            };
+
+            node = child;
+            rest = next;
+        }
+
+        node.is_end
+    }
+
+    pub fn delete(&mut self, word: &str) {
+        if !word.is_empty() {
+            Self::delete_rec(&mut self.root, word);
+        }
+    }
+
+    fn delete_rec(node: &mut RadixNode, word: &str) -> bool {
+        if word.is_empty() {
+            let found = node.is_end;
+            node.is_end = false;
+            return found;
+        }
+
+        let first = first_char(word);
+
+        let Some(child) = node.children.get_mut(&first) else {
+            return false;
+        };
+
+        let Some(rest) = word.strip_prefix(child.label.as_str()) else {
+            return false;
+        };
+
+        if !Self::delete_rec(child, rest) {
+            return false;
+        }
+
+        // post traversal
+        if child.is_empty() {
+            node.children.remove(&first);
+        } else {
+            child.merge_child();
+        }
+
+        true
+    }
+
+    pub fn clear(&mut self) {
+        self.root.children.clear();
+    }
+
+    pub fn node_count(&self) -> usize {
+        let mut count = 0;
+        let mut stack = vec![&self.root];
+
+        while let Some(node) = stack.pop() {
+            count += 1;
+            stack.extend(node.children.values());
+        }
+
+        count
+    }
+}
+
+impl From<&Trie> for RadixTrie {
+    fn from(trie: &Trie) -> Self {
+        let mut root = RadixNode::default();
+
+        for child in trie.root.children.values() {
+            root.children.insert(child.value, compress(child));
+        }
+
+        Self { root }
+    }
+}
+
+/// Builds the radix node for `node`, swallowing every single-child,
+/// non-terminal node below it.
+fn compress<V>(mut node: &TNode<V>) -> RadixNode {
+    let mut label = node.value.to_string();
+
+    while !node.is_end() && node.children.len() == 1 {
+        let Some(child) = node.children.values().next() else {
+            break;
+        };
+
+        label.push(child.value);
+        node = child;
+    }
+
+    let mut radix = RadixNode::new(label, node.is_end());
+
+    for child in node.children.values() {
+        radix.children.insert(child.value, compress(child));
+    }
+
+    radix
+}
+
+fn first_char(s: &str) -> char {
+    s.chars().next().unwrap_or_default()
+}
+
+fn common_prefix_len(a: &str, b: &str) -> usize {
+    a.char_indices()
+        .zip(b.chars())
+        .find(|((_, x), y)| x != y)
+        .map(|((i, _), _)| i)
+        .unwrap_or_else(|| a.len().min(b.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WORDS: [&str; 8] = [
+        "romane",
+        "romanus",
+        "romulus",
+        "rubens",
+        "ruber",
+        "rubicon",
+        "rubicundus",
+        "rom",
+    ];
+
+    #[test]
+    fn test_insert_contains_delete() {
+        let mut radix = RadixTrie::new();
+
+        for w in WORDS.iter() {
+            radix.insert(w);
+        }
+
+        for w in WORDS.iter() {
+            assert!(radix.contains(w), "should contain \"{}\"", w);
+        }
+
+        assert!(!radix.contains("r"));
+        assert!(!radix.contains("roma"));
+        assert!(!radix.contains("rubicundusx"));
+        assert!(!radix.contains(""));
+
+        radix.delete("rom");
+        assert!(!radix.contains("rom"));
+        assert!(radix.contains("romane"));
+
+        radix.delete("romulus");
+        // "rom" + "an" merged back into one edge
+        let r = &radix.root.children[&'r'];
+        assert_eq!(r.children[&'o'].label, "oman");
+
+        radix.delete("romane");
+        radix.delete("romanus");
+        // only the "rub" branch is left below "r", so they merge too
+        assert_eq!(radix.root.children[&'r'].label, "rub");
+        assert!(radix.contains("rubicon"));
+
+        radix.clear();
+        assert!(!radix.contains("rubens"));
+        assert_eq!(radix.node_count(), 1);
+    }
+
+    #[test]
+    fn test_from_trie() {
+        let trie: Trie = WORDS.iter().copied().collect();
+        let radix = RadixTrie::from(&trie);
+
+        for w in WORDS.iter() {
+            assert!(radix.contains(w), "should contain \"{}\"", w);
+        }
+
+        assert!(!radix.contains("rub"));
+
+        let mut inserted = RadixTrie::new();
+        WORDS.iter().for_each(|w| inserted.insert(w));
+
+        assert_eq!(radix.node_count(), inserted.node_count());
+        assert!(radix.node_count() < trie.node_count());
+    }
+}
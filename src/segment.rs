@@ -0,0 +1,320 @@
+use crate::{Cursor, Trie};
+
+#[cfg(feature = "unicode")]
+use crate::normalize;
+
+/// A piece of segmented text, as a byte range into the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Segment<'t> {
+    pub text: &'t str,
+    pub start: usize,
+    pub end: usize,
+    /// Whether the segment is a dictionary word, runs of unknown chars are
+    /// merged into one segment.
+    pub known: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SegmentMode {
+    /// Takes the longest word at each position (maximal munch).
+    #[default]
+    Greedy,
+    /// Picks the segmentation with the fewest unknown chars, and then the
+    /// fewest segments.
+    MinUnknown,
+    /// Every dictionary word at every position, ordered by start and then
+    /// length, so segments overlap. Chars no word covers are merged into
+    /// unknown segments. See [`Segmenter::prefixes_at`] for one position.
+    AllPrefixes,
+}
+
+/// Splits unspaced text into the words of a [`Trie`].
+///
+/// On a normalizing trie, each char is normalized together with the
+/// combining marks after it, so words end between such runs only.
+pub struct Segmenter<'a> {
+    trie: &'a Trie,
+    mode: SegmentMode,
+}
+
+impl<'a> Segmenter<'a> {
+    pub fn new(trie: &'a Trie, mode: SegmentMode) -> Self {
+        Self { trie, mode }
+    }
+
+    /// Returns the end offsets of every word starting at byte `start`,
+    /// shortest first.
+    fn word_ends(&self, text: &str, start: usize) -> Vec<usize> {
+        let rest = &text[start..];
+        let cursor = self.trie.map.cursor();
+
+        #[cfg(feature = "unicode")]
+        if let Some(policy) = self.trie.normalization() {
+            let units = normalize::units(rest)
+                .map(|(end, unit)| (end, policy.apply(unit).chars().collect::<Vec<_>>()));
+            return Self::ends_along(cursor, start, units);
+        }
+
+        let chars = rest.char_indices().map(|(i, ch)| (i + ch.len_utf8(), [ch]));
+        Self::ends_along(cursor, start, chars)
+    }
+
+    /// Follows `units`, each the offset it ends at past `start` and the
+    /// symbols to advance by, and returns the offsets where a word ends.
+    fn ends_along<C: IntoIterator<Item = char>>(
+        mut cursor: Cursor<'_>,
+        start: usize,
+        units: impl Iterator<Item = (usize, C)>,
+    ) -> Vec<usize> {
+        let mut ends = Vec::new();
+
+        for (end, symbols) in units {
+            if !symbols.into_iter().all(|ch| cursor.advance(ch)) {
+                break;
+            }
+
+            if cursor.is_word() {
+                ends.push(start + end);
+            }
+
+            if !cursor.has_children() {
+                break;
+            }
+        }
+
+        ends
+    }
+
+    /// Every dictionary word that starts at byte `start` of `text`,
+    /// shortest first.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `start` isn't on a char boundary.
+    pub fn prefixes_at<'t>(&self, text: &'t str, start: usize) -> Vec<Segment<'t>> {
+        self.word_ends(text, start)
+            .into_iter()
+            .map(|end| Segment {
+                text: &text[start..end],
+                start,
+                end,
+                known: true,
+            })
+            .collect()
+    }
+
+    pub fn segment<'t>(&self, text: &'t str) -> Vec<Segment<'t>> {
+        let ranges = match self.mode {
+            SegmentMode::Greedy => self.greedy(text),
+            SegmentMode::MinUnknown => self.min_unknown(text),
+            SegmentMode::AllPrefixes => self.all_prefixes(text),
+        };
+
+        let mut segments: Vec<Segment<'t>> = Vec::with_capacity(ranges.len());
+
+        for (start, end, known) in ranges {
+            match segments.last_mut() {
+                Some(last) if !known && !last.known => {
+                    last.end = end;
+                    last.text = &text[last.start..end];
+                }
+                _ => segments.push(Segment {
+                    text: &text[start..end],
+                    start,
+                    end,
+                    known,
+                }),
+            }
+        }
+
+        segments
+    }
+
+    fn greedy(&self, text: &str) -> Vec<(usize, usize, bool)> {
+        let mut ranges = Vec::new();
+        let mut start = 0;
+
+        while let Some(ch) = text[start..].chars().next() {
+            let range = match self.word_ends(text, start).last() {
+                Some(&end) => (start, end, true),
+                None => (start, start + ch.len_utf8(), false),
+            };
+
+            start = range.1;
+            ranges.push(range);
+        }
+
+        ranges
+    }
+
+    fn all_prefixes(&self, text: &str) -> Vec<(usize, usize, bool)> {
+        let mut ranges = Vec::new();
+        let mut covered = 0;
+
+        for (start, ch) in text.char_indices() {
+            let ends = self.word_ends(text, start);
+
+            if ends.is_empty() && start >= covered {
+                ranges.push((start, start + ch.len_utf8(), false));
+            }
+
+            for end in ends {
+                covered = covered.max(end);
+                ranges.push((start, end, true));
+            }
+        }
+
+        ranges
+    }
+
+    fn min_unknown(&self, text: &str) -> Vec<(usize, usize, bool)> {
+        // best[i] = (unknown chars, segments, previous offset, known) for
+        // the best segmentation of text[..i], only set on char boundaries
+        let mut best: Vec<Option<(usize, usize, usize, bool)>> = vec![None; text.len() + 1];
+        best[0] = Some((0, 0, 0, true));
+
+        for (start, ch) in text.char_indices() {
+            let Some((unknown, count, _, _)) = best[start] else {
+                continue;
+            };
+
+            let mut relax = |end: usize, cost: (usize, usize), known: bool| {
+                if best[end].is_none_or(|(u, c, _, _)| cost < (u, c)) {
+                    best[end] = Some((cost.0, cost.1, start, known));
+                }
+            };
+
+            relax(start + ch.len_utf8(), (unknown + 1, count + 1), false);
+
+            for end in self.word_ends(text, start) {
+                relax(end, (unknown, count + 1), true);
+            }
+        }
+
+        let mut ranges = Vec::new();
+        let mut end = text.len();
+
+        while end > 0 {
+            let Some((_, _, start, known)) = best[end] else {
+                break;
+            };
+
+            ranges.push((start, end, known));
+            end = start;
+        }
+
+        ranges.reverse();
+        ranges
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts<'t>(segments: &[Segment<'t>]) -> Vec<&'t str> {
+        segments.iter().map(|s| s.text).collect()
+    }
+
+    #[test]
+    fn test_greedy() {
+        let trie: Trie = [
+            "the",
+            "there",
+            "rein",
+            "in",
+            "kitchen",
+            "kit",
+            "北京",
+            "大学",
+            "北京大学",
+        ]
+        .iter()
+        .collect();
+        let segmenter = Segmenter::new(&trie, SegmentMode::Greedy);
+
+        let segments = segmenter.segment("thereinkitchen");
+        assert_eq!(texts(&segments), ["there", "in", "kitchen"]);
+        assert_eq!((segments[1].start, segments[1].end), (5, 7));
+
+        let segments = segmenter.segment("xx北京大学!");
+        assert_eq!(texts(&segments), ["xx", "北京大学", "!"]);
+        assert_eq!(segments[1].start, 2);
+        assert!(!segments[0].known && segments[1].known);
+
+        assert_eq!(segmenter.segment(""), []);
+    }
+
+    #[test]
+    fn test_min_unknown() {
+        let trie: Trie = ["the", "there", "a", "ink", "kitchen", "in"]
+            .iter()
+            .collect();
+
+        // greedy takes "there" and strands the "k" of "kitchen"
+        let greedy = Segmenter::new(&trie, SegmentMode::Greedy);
+        assert_eq!(
+            texts(&greedy.segment("thereinkitchen")),
+            ["there", "ink", "itchen"]
+        );
+
+        let dp = Segmenter::new(&trie, SegmentMode::MinUnknown);
+        let segments = dp.segment("thereinkitchen");
+        assert_eq!(texts(&segments), ["there", "in", "kitchen"]);
+        assert!(segments.iter().all(|s| s.known));
+
+        assert_eq!(texts(&dp.segment("zzthea")), ["zz", "the", "a"]);
+    }
+
+    #[test]
+    fn test_prefixes_at() {
+        let trie: Trie = ["北", "北京", "北京大学", "京"].iter().collect();
+        let segmenter = Segmenter::new(&trie, SegmentMode::default());
+
+        assert_eq!(
+            texts(&segmenter.prefixes_at("北京大学生", 0)),
+            ["北", "北京", "北京大学"]
+        );
+        assert_eq!(texts(&segmenter.prefixes_at("北京大学生", 3)), ["京"]);
+        assert_eq!(segmenter.prefixes_at("北京大学生", 12), []);
+    }
+
+    #[test]
+    fn test_all_prefixes() {
+        let trie: Trie = ["北", "北京", "北京大学", "京", "大学"].iter().collect();
+        let segmenter = Segmenter::new(&trie, SegmentMode::AllPrefixes);
+
+        let segments = segmenter.segment("x北京大学生y");
+        assert_eq!(
+            texts(&segments),
+            ["x", "北", "北京", "北京大学", "京", "大学", "生y"]
+        );
+        assert_eq!((segments[5].start, segments[5].end), (7, 13));
+        assert!(!segments[0].known && segments[1].known && !segments[6].known);
+
+        assert_eq!(segmenter.segment(""), []);
+    }
+
+    #[test]
+    #[cfg(feature = "unicode")]
+    fn test_normalized_segment() {
+        use crate::{Form, Normalization};
+
+        let policy = Normalization::new().form(Form::Nfc).case_fold(true);
+        let mut trie = Trie::with_normalization(policy);
+        for word in ["café", "au", "lait", "straße", "stras"] {
+            trie.insert(word);
+        }
+
+        let segmenter = Segmenter::new(&trie, SegmentMode::Greedy);
+        let text = "CAFE\u{301}AuLait";
+        let segments = segmenter.segment(text);
+        assert_eq!(texts(&segments), ["CAFE\u{301}", "Au", "Lait"]);
+        assert_eq!((segments[1].start, segments[1].end), (6, 8));
+
+        // "stras" ends halfway through the fold of ß, so it isn't a word
+        assert_eq!(texts(&segmenter.prefixes_at("STRAẞE!", 0)), ["STRAẞE"]);
+        assert_eq!(texts(&segmenter.segment("Straß")), ["Straß"]);
+        assert!(!segmenter.segment("Straß")[0].known);
+    }
+}
@@ -0,0 +1,361 @@
+//! Serde support, enabled by the `serde` feature.
+//!
+//! [`Trie`] serializes as a flat sequence of its words and [`TrieMap`] as
+//! a flat map from word to value, both in sorted order. The [`nested`]
+//! module serializes either one as its tree of nodes instead.
+
+use crate::{Key, Symbol, TNode, Trie, TrieMap};
+use serde::de::{MapAccess, SeqAccess, Visitor};
+use serde::ser::{Error, SerializeMap, SerializeSeq, SerializeStruct};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::marker::PhantomData;
+
+/// Children of a node, serialized as a map sorted by symbol.
+struct SortedChildren<'a, V, K: Symbol>(&'a TNode<V, K>);
+
+impl<'a, V: Serialize, K: Symbol + Serialize> Serialize for SortedChildren<'a, V, K> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut children: Vec<_> = self.0.children.values().collect();
+        children.sort_unstable_by(|a, b| a.value.cmp(&b.value));
+
+        let mut map = serializer.serialize_map(Some(children.len()))?;
+        for child in children {
+            map.serialize_entry(&child.value, child)?;
+        }
+        map.end()
+    }
+}
+
+/// A node is a struct whose `data` field is only present on terminal
+/// nodes. Its own symbol is the key it's stored under in its parent.
+impl<V: Serialize, K: Symbol + Serialize> Serialize for TNode<V, K> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut node = serializer.serialize_struct("TNode", 3)?;
+
+        match &self.data {
+            Some(data) => node.serialize_field("data", data)?,
+            None => node.skip_field("data")?,
+        }
+
+        if self.weight != 0 {
+            node.serialize_field("weight", &self.weight)?;
+        } else {
+            node.skip_field("weight")?;
+        }
+
+        if !self.children.is_empty() {
+            node.serialize_field("children", &SortedChildren(self))?;
+        } else {
+            node.skip_field("children")?;
+        }
+
+        node.end()
+    }
+}
+
+fn present<'de, D: Deserializer<'de>, V: Deserialize<'de>>(d: D) -> Result<Option<V>, D::Error> {
+    V::deserialize(d).map(Some)
+}
+
+#[derive(Deserialize)]
+#[serde(
+    rename = "TNode",
+    bound(deserialize = "V: Deserialize<'de>, K: Ord + Deserialize<'de>")
+)]
+struct NodeRepr<V, K> {
+    #[serde(default, deserialize_with = "present")]
+    data: Option<V>,
+    #[serde(default)]
+    weight: u64,
+    #[serde(default = "BTreeMap::new")]
+    children: BTreeMap<K, NodeRepr<V, K>>,
+}
+
+impl<V, K: Symbol> NodeRepr<V, K> {
+    /// Builds the node, pruning every subtree that holds no word.
+    fn into_node(self, value: K) -> TNode<V, K> {
+        let mut node: TNode<V, K> = TNode::new(value, self.data);
+        node.weight = if node.is_end() { self.weight } else { 0 };
+
+        for (key, child) in self.children {
+            let child = child.into_node(key.clone());
+
+            if !child.is_empty() {
+                node.children.insert(key, child);
+            }
+        }
+
+        node.update_max_weight();
+        node
+    }
+}
+
+impl<'de, V: Deserialize<'de>, K: Symbol + Deserialize<'de>> Deserialize<'de> for TNode<V, K> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        NodeRepr::deserialize(deserializer).map(|repr| repr.into_node(K::default()))
+    }
+}
+
+impl<K: Symbol> Serialize for Trie<K>
+where
+    K::Word: Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.check_serializable().map_err(S::Error::custom)?;
+
+        let mut seq = serializer.serialize_seq(Some(self.iter().len()))?;
+        for word in self.iter() {
+            seq.serialize_element(&word)?;
+        }
+        seq.end()
+    }
+}
+
+impl<'de, K: Symbol> Deserialize<'de> for Trie<K>
+where
+    K::Word: Deserialize<'de> + Key<K>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct WordsVisitor<K>(PhantomData<K>);
+
+        impl<'de, K: Symbol> Visitor<'de> for WordsVisitor<K>
+        where
+            K::Word: Deserialize<'de> + Key<K>,
+        {
+            type Value = Trie<K>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a sequence of words")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let mut trie = Trie::new();
+                while let Some(word) = seq.next_element::<K::Word>()? {
+                    trie.insert(&word);
+                }
+                Ok(trie)
+            }
+        }
+
+        deserializer.deserialize_seq(WordsVisitor(PhantomData))
+    }
+}
+
+impl<V: Serialize, K: Symbol> Serialize for TrieMap<V, K>
+where
+    K::Word: Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.iter().len()))?;
+        for (word, value) in self.iter() {
+            map.serialize_entry(&word, value)?;
+        }
+        map.end()
+    }
+}
+
+impl<'de, V: Deserialize<'de>, K: Symbol> Deserialize<'de> for TrieMap<V, K>
+where
+    K::Word: Deserialize<'de> + Key<K>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct EntriesVisitor<V, K>(PhantomData<(V, K)>);
+
+        impl<'de, V: Deserialize<'de>, K: Symbol> Visitor<'de> for EntriesVisitor<V, K>
+        where
+            K::Word: Deserialize<'de> + Key<K>,
+        {
+            type Value = TrieMap<V, K>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a map from words to values")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut trie = TrieMap::new();
+                while let Some((word, value)) = map.next_entry::<K::Word, V>()? {
+                    trie.insert(&word, value);
+                }
+                Ok(trie)
+            }
+        }
+
+        deserializer.deserialize_map(EntriesVisitor(PhantomData))
+    }
+}
+
+/// A trie type the [`nested`] representation applies to.
+pub trait NestedTrie {
+    type Value;
+    type Symbol: Symbol;
+
+    fn root(&self) -> &TNode<Self::Value, Self::Symbol>;
+
+    fn from_root(root: TNode<Self::Value, Self::Symbol>) -> Self;
+
+    /// Why the trie can't be serialized, if it can't.
+    fn check_serializable(&self) -> Result<(), &'static str> {
+        Ok(())
+    }
+}
+
+impl<K: Symbol> NestedTrie for Trie<K> {
+    type Value = ();
+    type Symbol = K;
+
+    fn root(&self) -> &TNode<(), K> {
+        &self.root
+    }
+
+    fn from_root(root: TNode<(), K>) -> Self {
+        Self::from_map(TrieMap::from_root(root))
+    }
+
+    fn check_serializable(&self) -> Result<(), &'static str> {
+        Trie::check_serializable(self)
+    }
+}
+
+impl<V, K: Symbol> NestedTrie for TrieMap<V, K> {
+    type Value = V;
+    type Symbol = K;
+
+    fn root(&self) -> &TNode<V, K> {
+        &self.root
+    }
+
+    fn from_root(mut root: TNode<V, K>) -> Self {
+        // the empty word is never stored
+        root.data = None;
+        root.weight = 0;
+        root.update_max_weight();
+
+        Self::with_root(root)
+    }
+}
+
+/// Serializes a [`Trie`] or [`TrieMap`] as its nested tree of nodes, for
+/// use with `#[serde(with = "trie_ferris::nested")]`.
+///
+/// Deeply nested tries may hit the recursion limit of some formats, the
+/// default flat representation has no such limit.
+pub mod nested {
+    use super::NestedTrie;
+    use crate::TNode;
+    use serde::ser::Error;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<T, S>(trie: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: NestedTrie,
+        TNode<T::Value, T::Symbol>: Serialize,
+        S: Serializer,
+    {
+        trie.check_serializable().map_err(S::Error::custom)?;
+        trie.root().serialize(serializer)
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: NestedTrie,
+        TNode<T::Value, T::Symbol>: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        TNode::deserialize(deserializer).map(T::from_root)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[derive(Serialize, Deserialize)]
+    struct Config {
+        words: Trie,
+        #[serde(with = "nested")]
+        tree: Trie,
+        #[serde(with = "nested")]
+        counts: TrieMap<u32>,
+    }
+
+    #[test]
+    fn test_flat() {
+        let trie: Trie = ["cut", "cat", "catch"].iter().collect();
+        let value = serde_json::to_value(&trie).unwrap();
+        assert_eq!(value, json!(["cat", "catch", "cut"]));
+
+        let decoded: Trie = serde_json::from_value(value).unwrap();
+        assert_eq!(decoded.iter().collect::<Vec<_>>(), ["cat", "catch", "cut"]);
+
+        let mut map = TrieMap::new();
+        map.insert("b", 2);
+        map.insert("a", 1);
+        let value = serde_json::to_value(&map).unwrap();
+        assert_eq!(value, json!({ "a": 1, "b": 2 }));
+
+        let decoded: TrieMap<i32> = serde_json::from_value(value).unwrap();
+        assert_eq!(decoded.get("b"), Some(&2));
+    }
+
+    #[test]
+    #[cfg(feature = "unicode")]
+    fn test_refuses_policy() {
+        use crate::Normalization;
+
+        let mut trie = Trie::with_normalization(Normalization::new().case_fold(true));
+        trie.insert("Cat");
+
+        assert!(serde_json::to_value(&trie).is_err());
+        assert!(nested::serialize(&trie, serde_json::value::Serializer).is_err());
+    }
+
+    #[test]
+    fn test_nested() {
+        let mut counts = TrieMap::new();
+        counts.insert("ab", 3);
+        counts.insert_weighted("a", 1, 5);
+
+        let config = Config {
+            words: ["ab"].iter().collect(),
+            tree: ["ab", "a"].iter().collect(),
+            counts,
+        };
+
+        let value = serde_json::to_value(&config).unwrap();
+        assert_eq!(
+            value["tree"],
+            json!({ "children": { "a": { "data": null, "children": { "b": { "data": null } } } } })
+        );
+        assert_eq!(
+            value["counts"]["children"]["a"],
+            json!({ "data": 1, "weight": 5, "children": { "b": { "data": 3 } } })
+        );
+
+        let decoded: Config = serde_json::from_value(value).unwrap();
+        assert!(decoded.tree.contains("a"));
+        assert!(decoded.tree.contains("ab"));
+        assert_eq!(decoded.counts.get("ab"), Some(&3));
+        assert_eq!(decoded.counts.root.max_weight, 5);
+        assert_eq!(decoded.words.iter().collect::<Vec<_>>(), ["ab"]);
+    }
+
+    #[test]
+    fn test_nested_prunes_dead_branches() {
+        let value = json!({
+            "data": null,
+            "children": {
+                "a": { "children": { "b": {}, "c": { "children": { "d": {} } } } },
+                "x": { "data": null, "children": { "y": {} } }
+            }
+        });
+
+        let trie: Trie = nested::deserialize(value).unwrap();
+        assert_eq!(trie.iter().collect::<Vec<_>>(), ["x"]);
+        assert!(!trie.root.has(&'a'));
+        assert!(trie.root.children[&'x'].children.is_empty());
+        assert!(!trie.root.is_end());
+    }
+}
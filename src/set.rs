@@ -0,0 +1,460 @@
+use crate::iter::count_words;
+use crate::{Symbol, TNode, Trie, TrieMap};
+
+#[cfg(feature = "unicode")]
+use crate::normalize::Normalizer;
+
+type Node<K> = TNode<(), K>;
+
+/// A childless copy of `node`, ending a word or not.
+fn shell<K: Symbol>(node: &Node<K>, is_end: bool, weight: u64) -> Node<K> {
+    let mut shell = TNode::new(node.value.clone(), is_end.then_some(()));
+    shell.weight = if is_end { weight } else { 0 };
+    shell
+}
+
+/// Finishes a node built by one of the walks, `None` if it holds no word.
+fn finish<K: Symbol>(mut node: Node<K>) -> Option<Node<K>> {
+    node.update_max_weight();
+    (!node.is_empty()).then_some(node)
+}
+
+/// A result node under construction, and the pairs of children still to
+/// walk into.
+struct Frame<K: Symbol, A, B> {
+    node: Node<K>,
+    pairs: Vec<(A, B)>,
+}
+
+/// Walks pairs of nodes depth first, with an explicit stack instead of
+/// recursing once per symbol. `open` turns a pair into the frame of its
+/// result node; each node is finished once every pair under it is, and
+/// hung under its parent unless it ends up holding no word.
+fn walk<K: Symbol, A, B>(
+    a: A,
+    b: B,
+    mut open: impl FnMut(A, B) -> Frame<K, A, B>,
+) -> Option<Node<K>> {
+    let mut stack = vec![open(a, b)];
+
+    while let Some(frame) = stack.last_mut() {
+        if let Some((a, b)) = frame.pairs.pop() {
+            let frame = open(a, b);
+            stack.push(frame);
+            continue;
+        }
+
+        let node = stack.pop().and_then(|frame| finish(frame.node));
+
+        match stack.last_mut() {
+            Some(parent) => {
+                if let Some(node) = node {
+                    parent.node.children.insert(node.value.clone(), node);
+                }
+            }
+            None => return node,
+        }
+    }
+
+    None
+}
+
+fn union<'a, K: Symbol>(a: &'a Node<K>, b: &'a Node<K>) -> Frame<K, &'a Node<K>, &'a Node<K>> {
+    let mut node = shell(a, a.is_end() || b.is_end(), a.weight.max(b.weight));
+    let mut pairs = Vec::new();
+
+    for (key, child) in &a.children {
+        match b.get(key) {
+            Some(other) => pairs.push((child, other)),
+            None => {
+                node.children.insert(key.clone(), child.clone());
+            }
+        }
+    }
+
+    for (key, child) in &b.children {
+        if !a.has(key) {
+            node.children.insert(key.clone(), child.clone());
+        }
+    }
+
+    Frame { node, pairs }
+}
+
+fn intersection<'a, K: Symbol>(
+    a: &'a Node<K>,
+    b: &'a Node<K>,
+) -> Frame<K, &'a Node<K>, &'a Node<K>> {
+    let node = shell(a, a.is_end() && b.is_end(), a.weight);
+
+    // only keys on both sides can hold common words
+    let pairs = a
+        .children
+        .iter()
+        .filter_map(|(key, child)| Some((child, b.get(key)?)))
+        .collect();
+
+    Frame { node, pairs }
+}
+
+fn difference<'a, K: Symbol>(a: &'a Node<K>, b: &'a Node<K>) -> Frame<K, &'a Node<K>, &'a Node<K>> {
+    let mut node = shell(a, a.is_end() && !b.is_end(), a.weight);
+    let mut pairs = Vec::new();
+
+    for (key, child) in &a.children {
+        match b.get(key) {
+            Some(other) => pairs.push((child, other)),
+            None => {
+                node.children.insert(key.clone(), child.clone());
+            }
+        }
+    }
+
+    Frame { node, pairs }
+}
+
+fn symmetric_difference<'a, K: Symbol>(
+    a: &'a Node<K>,
+    b: &'a Node<K>,
+) -> Frame<K, &'a Node<K>, &'a Node<K>> {
+    let weight = if a.is_end() { a.weight } else { b.weight };
+    let mut node = shell(a, a.is_end() != b.is_end(), weight);
+    let mut pairs = Vec::new();
+
+    for (key, child) in &a.children {
+        match b.get(key) {
+            Some(other) => pairs.push((child, other)),
+            None => {
+                node.children.insert(key.clone(), child.clone());
+            }
+        }
+    }
+
+    for (key, child) in &b.children {
+        if !a.has(key) {
+            node.children.insert(key.clone(), child.clone());
+        }
+    }
+
+    Frame { node, pairs }
+}
+
+/// Moves the words of `b` into `a`, returning how many of them `a`
+/// already held.
+fn merge_into<K: Symbol>(a: &mut Node<K>, b: Node<K>) -> usize {
+    let mut shared = 0;
+    let root = std::mem::replace(a, TNode::new(K::default(), None));
+
+    let merged = walk(root, b, |mut a: Node<K>, b: Node<K>| {
+        shared += (a.is_end() && b.is_end()) as usize;
+
+        if b.is_end() {
+            a.data = Some(());
+            a.weight = a.weight.max(b.weight);
+        }
+
+        let mut pairs = Vec::new();
+
+        for (key, child) in b.children {
+            match a.children.remove(&key) {
+                Some(ours) => pairs.push((ours, child)),
+                // moved over whole, without walking it
+                None => {
+                    a.children.insert(key, child);
+                }
+            }
+        }
+
+        Frame { node: a, pairs }
+    });
+
+    if let Some(merged) = merged {
+        *a = merged;
+    }
+
+    shared
+}
+
+/// Drops the words of `a` that aren't in `b`, returning how many.
+fn retain_in<K: Symbol>(a: &mut Node<K>, b: &Node<K>) -> usize {
+    let mut removed = 0;
+    let root = std::mem::replace(a, TNode::new(K::default(), None));
+
+    let kept = walk(root, b, |mut a: Node<K>, b: &Node<K>| {
+        removed += (a.is_end() && !b.is_end()) as usize;
+
+        if !b.is_end() {
+            a.data = None;
+            a.weight = 0;
+        }
+
+        let mut pairs = Vec::new();
+
+        for (key, child) in std::mem::take(&mut a.children) {
+            match b.get(&key) {
+                Some(other) => pairs.push((child, other)),
+                None => removed += count_words(&child),
+            }
+        }
+
+        Frame { node: a, pairs }
+    });
+
+    if let Some(kept) = kept {
+        *a = kept;
+    }
+
+    removed
+}
+
+/// Drops the words of `a` that are in `b`, returning how many.
+fn remove_all_in<K: Symbol>(a: &mut Node<K>, b: &Node<K>) -> usize {
+    let mut removed = 0;
+    let root = std::mem::replace(a, TNode::new(K::default(), None));
+
+    let kept = walk(root, b, |mut a: Node<K>, b: &Node<K>| {
+        removed += (a.is_end() && b.is_end()) as usize;
+
+        if b.is_end() {
+            a.data = None;
+            a.weight = 0;
+        }
+
+        let pairs = b
+            .children
+            .iter()
+            .filter_map(|(key, other)| Some((a.children.remove(key)?, other)))
+            .collect();
+
+        Frame { node: a, pairs }
+    });
+
+    if let Some(kept) = kept {
+        *a = kept;
+    }
+
+    removed
+}
+
+/// Every word under `root`, with its weight.
+#[cfg(feature = "unicode")]
+fn weighted_words<K: Symbol>(root: &Node<K>) -> Vec<(Vec<K>, u64)> {
+    let mut words = Vec::new();
+    let mut stack = vec![(root, 0)];
+    let mut word = Vec::new();
+
+    while let Some((node, depth)) = stack.pop() {
+        if depth > 0 {
+            word.truncate(depth - 1);
+            word.push(node.value.clone());
+        }
+
+        if node.is_end() {
+            words.push((word.clone(), node.weight));
+        }
+
+        stack.extend(node.children.values().map(|child| (child, depth + 1)));
+    }
+
+    words
+}
+
+/// Set operations walk both tries side by side, matching children by key.
+/// Subtrees found on one side only are copied or dropped as a whole.
+///
+/// A new trie takes the normalization policy of `self`, along with the
+/// spellings either side kept for its words when both share that policy.
+impl<K: Symbol> Trie<K> {
+    /// Words in `self` or `other`. A word in both keeps the larger weight.
+    pub fn union(&self, other: &Self) -> Self {
+        self.set_result(other, walk(&self.root, &other.root, union))
+    }
+
+    /// Words in both `self` and `other`, with the weights of `self`.
+    pub fn intersection(&self, other: &Self) -> Self {
+        self.set_result(other, walk(&self.root, &other.root, intersection))
+    }
+
+    /// Words in `self` but not in `other`.
+    pub fn difference(&self, other: &Self) -> Self {
+        self.set_result(other, walk(&self.root, &other.root, difference))
+    }
+
+    /// Words in exactly one of `self` and `other`.
+    pub fn symmetric_difference(&self, other: &Self) -> Self {
+        self.set_result(other, walk(&self.root, &other.root, symmetric_difference))
+    }
+
+    /// Wraps the root a set operation built, `None` if it holds no word.
+    fn set_result(&self, other: &Self, root: Option<Node<K>>) -> Self {
+        let root = root.unwrap_or_else(|| TNode::new(K::default(), None));
+        let trie = Self::from_map(TrieMap::with_root(root));
+
+        #[cfg(feature = "unicode")]
+        if let Some(ours) = &self.normalizer {
+            let mut normalizer = ours.clone();
+
+            if let Some(theirs) = &other.normalizer {
+                if self.normalization() == other.normalization() {
+                    normalizer.append(Normalizer::clone(theirs));
+                }
+            }
+
+            let mut trie = trie;
+            trie.normalizer = Some(normalizer);
+            trie.prune_spellings();
+            return trie;
+        }
+
+        #[cfg(not(feature = "unicode"))]
+        let _ = other;
+
+        trie
+    }
+
+    /// Adds every word of `other`, moving over the subtrees `self` lacks.
+    ///
+    /// If `self` normalizes words and `other` doesn't, or does so under
+    /// another policy, its words are inserted one by one instead, under
+    /// the spellings `other` kept for them.
+    pub fn extend_from(&mut self, other: Self) {
+        #[cfg(feature = "unicode")]
+        if self.normalizer.is_some() && self.normalization() != other.normalization() {
+            for (word, weight) in weighted_words(&other.map.root) {
+                let spellings = match &other.normalizer {
+                    Some(normalizer) => normalizer.spellings_of(&word),
+                    None => vec![word],
+                };
+
+                for spelling in spellings {
+                    // a word in both keeps the larger weight, as in a merge
+                    let current = self.weight(&spelling).unwrap_or(0);
+                    self.insert_weighted(&spelling, weight.saturating_sub(current));
+                }
+            }
+
+            return;
+        }
+
+        let added = other.len() - merge_into(&mut self.map.root, other.map.root);
+        self.map.len += added;
+
+        #[cfg(feature = "unicode")]
+        if let (Some(ours), Some(theirs)) = (&mut self.normalizer, other.normalizer) {
+            ours.append(*theirs);
+        }
+    }
+
+    /// Keeps only the words that are also in `other`.
+    pub fn retain_in(&mut self, other: &Self) {
+        self.map.len -= retain_in(&mut self.map.root, &other.root);
+        #[cfg(feature = "unicode")]
+        self.prune_spellings();
+    }
+
+    /// Removes every word that is in `other`.
+    pub fn remove_all_in(&mut self, other: &Self) {
+        self.map.len -= remove_all_in(&mut self.map.root, &other.root);
+        #[cfg(feature = "unicode")]
+        self.prune_spellings();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tries() -> (Trie, Trie) {
+        let system: Trie = ["cat", "catch", "cut", "dog", "door"].iter().collect();
+        let user: Trie = ["cat", "ca", "cute", "door", "zebra"].iter().collect();
+        (system, user)
+    }
+
+    fn words(trie: &Trie) -> Vec<String> {
+        trie.iter().collect()
+    }
+
+    #[test]
+    fn test_new_tries() {
+        let (system, user) = tries();
+
+        assert_eq!(
+            words(&system.union(&user)),
+            ["ca", "cat", "catch", "cut", "cute", "dog", "door", "zebra"]
+        );
+        assert_eq!(words(&system.intersection(&user)), ["cat", "door"]);
+        assert_eq!(words(&system.difference(&user)), ["catch", "cut", "dog"]);
+        assert_eq!(words(&user.difference(&system)), ["ca", "cute", "zebra"]);
+        assert_eq!(
+            words(&system.symmetric_difference(&user)),
+            ["ca", "catch", "cut", "cute", "dog", "zebra"]
+        );
+
+        // pruned: no dead branch is left for "dog" or "cu"
+        let common = system.intersection(&user);
+        assert_eq!(common.node_count(), 1 + 3 + 4);
+        assert!(system.intersection(&Trie::new()).root.children.is_empty());
+    }
+
+    #[test]
+    fn test_in_place() {
+        let (system, user) = tries();
+
+        let mut merged = system.clone();
+        merged.extend_from(user.clone());
+        assert_eq!(words(&merged), words(&system.union(&user)));
+
+        let mut common = system.clone();
+        common.retain_in(&user);
+        assert_eq!(words(&common), ["cat", "door"]);
+        assert_eq!(common.node_count(), 8);
+
+        let mut unique = system.clone();
+        unique.remove_all_in(&user);
+        assert_eq!(words(&unique), ["catch", "cut", "dog"]);
+        assert!(unique.contains("catch") && !unique.contains("cat"));
+    }
+
+    #[test]
+    fn test_long_words() {
+        let long = "a".repeat(1_000);
+        let a: Trie = [long.as_str(), "b"].iter().collect();
+        let b: Trie = [long.as_str(), "c"].iter().collect();
+
+        assert_eq!(a.union(&b).len(), 3);
+        assert_eq!(words(&a.intersection(&b)), [long.as_str()]);
+        assert_eq!(words(&a.difference(&b)), ["b"]);
+        assert_eq!(words(&a.symmetric_difference(&b)), ["b", "c"]);
+
+        let mut merged = a.clone();
+        merged.extend_from(b.clone());
+        assert_eq!(merged.len(), 3);
+
+        merged.retain_in(&a);
+        assert_eq!(merged.len(), 2);
+        merged.remove_all_in(&b);
+        assert_eq!(words(&merged), ["b"]);
+        assert_eq!(merged.node_count(), 2);
+    }
+
+    #[test]
+    fn test_weights() {
+        let mut a = Trie::new();
+        a.insert_weighted("cat", 5);
+        a.insert_weighted("dog", 1);
+
+        let mut b = Trie::new();
+        b.insert_weighted("cat", 2);
+        b.insert_weighted("cow", 9);
+
+        assert_eq!(a.union(&b).weight("cat"), Some(5));
+        assert_eq!(a.union(&b).root.max_weight, 9);
+        assert_eq!(a.difference(&b).root.max_weight, 1);
+
+        a.extend_from(b);
+        assert_eq!(
+            a.top_k("c", 2),
+            [("cow".to_string(), 9), ("cat".to_string(), 5)]
+        );
+    }
+}
@@ -0,0 +1,136 @@
+use crate::store::{ChildStore, Children};
+use crate::{Symbol, TrieMap};
+use std::collections::BTreeMap;
+
+/// Shape and memory footprint of a trie, from [`TrieMap::stats`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrieStats {
+    pub words: usize,
+    /// Every node, including the root.
+    pub nodes: usize,
+    pub terminal_nodes: usize,
+    /// Nodes without children. The root of an empty trie counts as one.
+    pub leaf_nodes: usize,
+    /// Length of the longest word, in symbols.
+    pub max_depth: usize,
+    /// Mean length of the stored words, `0.0` for an empty trie.
+    pub avg_depth: f64,
+    /// Number of nodes by child count.
+    pub branching: BTreeMap<usize, usize>,
+    /// Estimated heap usage of the child tables, which hold every node but
+    /// the root. Heap memory owned by the values themselves isn't counted.
+    pub heap_bytes: usize,
+}
+
+impl<V, K: Symbol, S: ChildStore<K>> TrieMap<V, K, S> {
+    /// Walks the whole trie once to gather its [`TrieStats`].
+    pub fn stats(&self) -> TrieStats {
+        let mut stats = TrieStats {
+            words: self.len,
+            nodes: 0,
+            terminal_nodes: 0,
+            leaf_nodes: 0,
+            max_depth: 0,
+            avg_depth: 0.0,
+            branching: BTreeMap::new(),
+            heap_bytes: 0,
+        };
+        let mut total_depth = 0;
+        let mut stack = vec![(&self.root, 0)];
+
+        while let Some((node, depth)) = stack.pop() {
+            stats.nodes += 1;
+            stats.max_depth = stats.max_depth.max(depth);
+            stats.heap_bytes += node.children.heap_bytes();
+            *stats.branching.entry(node.children.len()).or_default() += 1;
+
+            if node.is_end() {
+                stats.terminal_nodes += 1;
+                total_depth += depth;
+            }
+
+            if node.children.is_empty() {
+                stats.leaf_nodes += 1;
+            }
+
+            stack.extend(node.children.values().map(|child| (child, depth + 1)));
+        }
+
+        if stats.terminal_nodes > 0 {
+            stats.avg_depth = total_depth as f64 / stats.terminal_nodes as f64;
+        }
+
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Trie;
+
+    #[test]
+    fn test_stats() {
+        let mut trie: Trie = ["cat", "catch", "cut", "do"].iter().collect();
+        let stats = trie.stats();
+
+        assert_eq!(stats.words, 4);
+        assert_eq!(stats.nodes, 10);
+        assert_eq!(stats.terminal_nodes, 4);
+        assert_eq!(stats.leaf_nodes, 3);
+        assert_eq!(stats.max_depth, 5);
+        assert_eq!(stats.avg_depth, 3.25);
+        assert_eq!(
+            stats.branching.into_iter().collect::<Vec<_>>(),
+            [(0, 3), (1, 5), (2, 2)]
+        );
+        assert!(stats.heap_bytes > 0);
+
+        trie.clear();
+        let stats = trie.stats();
+        assert_eq!((stats.words, stats.nodes, stats.leaf_nodes), (0, 1, 1));
+        assert_eq!(stats.avg_depth, 0.0);
+    }
+
+    #[test]
+    fn test_len_in_sync() {
+        let mut trie = Trie::new();
+        assert!(trie.is_empty());
+
+        trie.insert("cat");
+        trie.insert("cat");
+        trie.insert("");
+        trie.insert_weighted("catch", 3);
+        trie.insert_weighted("cat", 1);
+        assert_eq!(trie.len(), 2);
+
+        trie.delete("ca");
+        trie.delete("cat");
+        trie.delete("cat");
+        assert_eq!(trie.len(), 1);
+
+        let other: Trie = ["catch", "dog", "do"].iter().collect();
+        trie.extend_from(other.clone());
+        assert_eq!(trie.len(), 3);
+        assert_eq!(trie.union(&other).len(), 3);
+        assert_eq!(trie.difference(&["dog"].iter().collect()).len(), 2);
+
+        trie.remove_all_in(&["do", "dot"].iter().collect());
+        assert_eq!(trie.len(), 2);
+        trie.retain_in(&["dog", "d"].iter().collect());
+        assert_eq!(trie.len(), 1);
+
+        let mut map = crate::TrieMap::new();
+        *map.entry("a").or_insert(0) += 1;
+        *map.entry("a").or_insert(0) += 1;
+        map.entry("ab").or_insert(0);
+        assert_eq!(map.len(), 2);
+
+        for word in ["x", "y"] {
+            trie.insert(word);
+        }
+        assert_eq!(trie.len(), trie.iter().count());
+
+        trie.clear();
+        assert_eq!(trie.len(), 0);
+    }
+}
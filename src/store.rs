@@ -0,0 +1,593 @@
+//! Child-storage backends for [`TNode`](crate::TNode).
+//!
+//! A backend is a marker type implementing [`ChildStore`], picked as the
+//! last type parameter of [`Trie`](crate::Trie), [`TrieMap`](crate::TrieMap)
+//! and [`TNode`](crate::TNode):
+//!
+//! | backend      | child table                 | good for                        |
+//! |--------------|-----------------------------|---------------------------------|
+//! | [`Hashed`]   | `FxHashMap`                 | wide nodes, the default         |
+//! | [`SortedVec`]| `Vec` sorted by symbol      | the many nodes with one child   |
+//! | [`Ordered`]  | `BTreeMap`                  | ordered walks over wide nodes   |
+//! | [`AsciiLower`] | 26 slots, `'a'..='z'` only | dense lowercase alphabets       |
+//!
+//! The core API (insertion, lookup, removal, iteration, entries, weights,
+//! cursors and stats) works with every backend. The other modules build on
+//! the default [`Hashed`] tables.
+
+use crate::{FxHashMap, Symbol};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::mem::size_of;
+
+/// A family of child tables, generic over the node type they hold.
+pub trait ChildStore<K> {
+    type Map<T>: Children<K, T>;
+
+    /// Whether a child can be stored under `symbol`. Inserting a word with
+    /// a rejected symbol panics, `try_insert` checks for them first.
+    fn accepts(symbol: &K) -> bool {
+        let _ = symbol;
+        true
+    }
+}
+
+/// A word holds a symbol its backend can't store, see
+/// [`ChildStore::accepts`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnsupportedSymbol<K>(pub K);
+
+impl<K: fmt::Debug> fmt::Display for UnsupportedSymbol<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} is outside the alphabet of the child store", self.0)
+    }
+}
+
+impl<K: fmt::Debug> std::error::Error for UnsupportedSymbol<K> {}
+
+/// A table from symbols to child nodes.
+pub trait Children<K, T>: Default {
+    fn get(&self, key: &K) -> Option<&T>;
+
+    fn get_mut(&mut self, key: &K) -> Option<&mut T>;
+
+    fn get_or_insert_with<F: FnOnce() -> T>(&mut self, key: K, default: F) -> &mut T;
+
+    fn insert(&mut self, key: K, child: T) -> Option<T>;
+
+    fn remove(&mut self, key: &K) -> Option<T>;
+
+    fn retain<F: FnMut(&K, &mut T) -> bool>(&mut self, f: F);
+
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    fn clear(&mut self);
+
+    fn iter<'a>(&'a self) -> impl Iterator<Item = (&'a K, &'a T)>
+    where
+        K: 'a,
+        T: 'a;
+
+    fn values<'a>(&'a self) -> impl Iterator<Item = &'a T>
+    where
+        K: 'a,
+        T: 'a,
+    {
+        self.iter().map(|(_, child)| child)
+    }
+
+    fn values_mut<'a>(&'a mut self) -> impl Iterator<Item = &'a mut T>
+    where
+        T: 'a;
+
+    /// Clones the table, cloning children with `f`. Nodes clone through
+    /// this instead of a `Clone` bound, which would be cyclic.
+    fn clone_with<F: FnMut(&T) -> T>(&self, f: F) -> Self;
+
+    /// Estimated bytes allocated by the table itself, children included
+    /// but not what they own in turn.
+    fn heap_bytes(&self) -> usize;
+}
+
+/// Children in an `FxHashMap`, the default backend.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Hashed;
+
+impl<K: Symbol> ChildStore<K> for Hashed {
+    type Map<T> = FxHashMap<K, T>;
+}
+
+impl<K: Symbol, T> Children<K, T> for FxHashMap<K, T> {
+    fn get(&self, key: &K) -> Option<&T> {
+        self.get(key)
+    }
+
+    fn get_mut(&mut self, key: &K) -> Option<&mut T> {
+        self.get_mut(key)
+    }
+
+    fn get_or_insert_with<F: FnOnce() -> T>(&mut self, key: K, default: F) -> &mut T {
+        self.entry(key).or_insert_with(default)
+    }
+
+    fn insert(&mut self, key: K, child: T) -> Option<T> {
+        self.insert(key, child)
+    }
+
+    fn remove(&mut self, key: &K) -> Option<T> {
+        self.remove(key)
+    }
+
+    fn retain<F: FnMut(&K, &mut T) -> bool>(&mut self, f: F) {
+        self.retain(f)
+    }
+
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn clear(&mut self) {
+        self.clear()
+    }
+
+    fn iter<'a>(&'a self) -> impl Iterator<Item = (&'a K, &'a T)>
+    where
+        K: 'a,
+        T: 'a,
+    {
+        self.iter()
+    }
+
+    fn values_mut<'a>(&'a mut self) -> impl Iterator<Item = &'a mut T>
+    where
+        T: 'a,
+    {
+        self.values_mut()
+    }
+
+    fn clone_with<F: FnMut(&T) -> T>(&self, mut f: F) -> Self {
+        let mut table = Self::with_capacity_and_hasher(self.len(), Default::default());
+        table.extend(self.iter().map(|(key, child)| (key.clone(), f(child))));
+        table
+    }
+
+    /// Follows the `hashbrown` layout: one slot and one control byte per
+    /// bucket, plus a trailing group of control bytes.
+    fn heap_bytes(&self) -> usize {
+        const GROUP_WIDTH: usize = 16;
+
+        let capacity = self.capacity();
+
+        if capacity == 0 {
+            return 0;
+        }
+
+        let buckets = if capacity < 8 {
+            capacity + 1
+        } else {
+            (capacity * 8 / 7).next_power_of_two()
+        };
+
+        buckets * (size_of::<(K, T)>() + 1) + GROUP_WIDTH
+    }
+}
+
+/// Children in a `Vec` sorted by symbol, searched by bisection.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SortedVec;
+
+/// The child table of the [`SortedVec`] backend.
+#[derive(Debug)]
+pub struct VecChildren<K, T> {
+    entries: Vec<(K, T)>,
+}
+
+impl<K, T> Default for VecChildren<K, T> {
+    fn default() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+}
+
+impl<K: Symbol> ChildStore<K> for SortedVec {
+    type Map<T> = VecChildren<K, T>;
+}
+
+impl<K: Ord, T> VecChildren<K, T> {
+    fn position(&self, key: &K) -> Result<usize, usize> {
+        self.entries.binary_search_by(|(k, _)| k.cmp(key))
+    }
+}
+
+impl<K: Symbol, T> Children<K, T> for VecChildren<K, T> {
+    fn get(&self, key: &K) -> Option<&T> {
+        let i = self.position(key).ok()?;
+        Some(&self.entries[i].1)
+    }
+
+    fn get_mut(&mut self, key: &K) -> Option<&mut T> {
+        let i = self.position(key).ok()?;
+        Some(&mut self.entries[i].1)
+    }
+
+    fn get_or_insert_with<F: FnOnce() -> T>(&mut self, key: K, default: F) -> &mut T {
+        let i = match self.position(&key) {
+            Ok(i) => i,
+            Err(i) => {
+                self.entries.insert(i, (key, default()));
+                i
+            }
+        };
+
+        &mut self.entries[i].1
+    }
+
+    fn insert(&mut self, key: K, child: T) -> Option<T> {
+        match self.position(&key) {
+            Ok(i) => Some(std::mem::replace(&mut self.entries[i].1, child)),
+            Err(i) => {
+                self.entries.insert(i, (key, child));
+                None
+            }
+        }
+    }
+
+    fn remove(&mut self, key: &K) -> Option<T> {
+        let i = self.position(key).ok()?;
+        Some(self.entries.remove(i).1)
+    }
+
+    fn retain<F: FnMut(&K, &mut T) -> bool>(&mut self, mut f: F) {
+        self.entries.retain_mut(|(key, child)| f(key, child));
+    }
+
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear()
+    }
+
+    fn iter<'a>(&'a self) -> impl Iterator<Item = (&'a K, &'a T)>
+    where
+        K: 'a,
+        T: 'a,
+    {
+        self.entries.iter().map(|(key, child)| (key, child))
+    }
+
+    fn values_mut<'a>(&'a mut self) -> impl Iterator<Item = &'a mut T>
+    where
+        T: 'a,
+    {
+        self.entries.iter_mut().map(|(_, child)| child)
+    }
+
+    fn clone_with<F: FnMut(&T) -> T>(&self, mut f: F) -> Self {
+        Self {
+            entries: self
+                .entries
+                .iter()
+                .map(|(key, child)| (key.clone(), f(child)))
+                .collect(),
+        }
+    }
+
+    fn heap_bytes(&self) -> usize {
+        self.entries.capacity() * size_of::<(K, T)>()
+    }
+}
+
+/// Children in a `BTreeMap`, kept in symbol order.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Ordered;
+
+impl<K: Symbol> ChildStore<K> for Ordered {
+    type Map<T> = BTreeMap<K, T>;
+}
+
+impl<K: Symbol, T> Children<K, T> for BTreeMap<K, T> {
+    fn get(&self, key: &K) -> Option<&T> {
+        self.get(key)
+    }
+
+    fn get_mut(&mut self, key: &K) -> Option<&mut T> {
+        self.get_mut(key)
+    }
+
+    fn get_or_insert_with<F: FnOnce() -> T>(&mut self, key: K, default: F) -> &mut T {
+        self.entry(key).or_insert_with(default)
+    }
+
+    fn insert(&mut self, key: K, child: T) -> Option<T> {
+        self.insert(key, child)
+    }
+
+    fn remove(&mut self, key: &K) -> Option<T> {
+        self.remove(key)
+    }
+
+    fn retain<F: FnMut(&K, &mut T) -> bool>(&mut self, mut f: F) {
+        self.retain(|key, child| f(key, child))
+    }
+
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn clear(&mut self) {
+        self.clear()
+    }
+
+    fn iter<'a>(&'a self) -> impl Iterator<Item = (&'a K, &'a T)>
+    where
+        K: 'a,
+        T: 'a,
+    {
+        self.iter()
+    }
+
+    fn values_mut<'a>(&'a mut self) -> impl Iterator<Item = &'a mut T>
+    where
+        T: 'a,
+    {
+        self.values_mut()
+    }
+
+    fn clone_with<F: FnMut(&T) -> T>(&self, mut f: F) -> Self {
+        self.iter()
+            .map(|(key, child)| (key.clone(), f(child)))
+            .collect()
+    }
+
+    /// Counts full B-tree leaves of 11 entries plus their parent links and
+    /// lengths; internal nodes are rare for child tables and ignored.
+    fn heap_bytes(&self) -> usize {
+        const CAPACITY: usize = 11;
+
+        let leaves = self.len().div_ceil(CAPACITY);
+        leaves * (CAPACITY * (size_of::<K>() + size_of::<T>()) + 2 * size_of::<usize>())
+    }
+}
+
+/// Children in 26 boxed slots, one per letter `'a'..='z'`.
+///
+/// Only `char` tries with ASCII-lowercase words can use it: inserting any
+/// other symbol panics, and looking one up finds nothing. Use
+/// [`Trie::try_insert`](crate::Trie::try_insert) for words that aren't
+/// known to fit.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AsciiLower;
+
+/// The child table of the [`AsciiLower`] backend.
+#[derive(Debug)]
+pub struct ArrayChildren<T> {
+    slots: [Option<Box<T>>; 26],
+}
+
+impl<T> Default for ArrayChildren<T> {
+    fn default() -> Self {
+        Self {
+            slots: Default::default(),
+        }
+    }
+}
+
+impl ChildStore<char> for AsciiLower {
+    type Map<T> = ArrayChildren<T>;
+
+    fn accepts(symbol: &char) -> bool {
+        slot(symbol).is_some()
+    }
+}
+
+const LETTERS: [char; 26] = [
+    'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', 'j', 'k', 'l', 'm', 'n', 'o', 'p', 'q', 'r', 's',
+    't', 'u', 'v', 'w', 'x', 'y', 'z',
+];
+
+fn slot(key: &char) -> Option<usize> {
+    key.is_ascii_lowercase()
+        .then(|| (*key as u8 - b'a') as usize)
+}
+
+fn expect_slot(key: &char) -> usize {
+    slot(key).unwrap_or_else(|| panic!("{key:?} is outside the 'a'..='z' alphabet"))
+}
+
+impl<T> Children<char, T> for ArrayChildren<T> {
+    fn get(&self, key: &char) -> Option<&T> {
+        self.slots[slot(key)?].as_deref()
+    }
+
+    fn get_mut(&mut self, key: &char) -> Option<&mut T> {
+        self.slots[slot(key)?].as_deref_mut()
+    }
+
+    fn get_or_insert_with<F: FnOnce() -> T>(&mut self, key: char, default: F) -> &mut T {
+        self.slots[expect_slot(&key)].get_or_insert_with(|| Box::new(default()))
+    }
+
+    fn insert(&mut self, key: char, child: T) -> Option<T> {
+        self.slots[expect_slot(&key)]
+            .replace(Box::new(child))
+            .map(|old| *old)
+    }
+
+    fn remove(&mut self, key: &char) -> Option<T> {
+        self.slots[slot(key)?].take().map(|old| *old)
+    }
+
+    fn retain<F: FnMut(&char, &mut T) -> bool>(&mut self, mut f: F) {
+        for (key, slot) in LETTERS.iter().zip(&mut self.slots) {
+            if slot.as_deref_mut().is_some_and(|child| !f(key, child)) {
+                *slot = None;
+            }
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.slots.iter().flatten().count()
+    }
+
+    fn clear(&mut self) {
+        self.slots = Default::default();
+    }
+
+    fn iter<'a>(&'a self) -> impl Iterator<Item = (&'a char, &'a T)>
+    where
+        T: 'a,
+    {
+        LETTERS
+            .iter()
+            .zip(&self.slots)
+            .filter_map(|(key, slot)| Some((key, slot.as_deref()?)))
+    }
+
+    fn values_mut<'a>(&'a mut self) -> impl Iterator<Item = &'a mut T>
+    where
+        T: 'a,
+    {
+        self.slots.iter_mut().flatten().map(|child| &mut **child)
+    }
+
+    fn clone_with<F: FnMut(&T) -> T>(&self, mut f: F) -> Self {
+        let mut table = Self::default();
+
+        for (slot, child) in table.slots.iter_mut().zip(&self.slots) {
+            *slot = child.as_deref().map(|child| Box::new(f(child)));
+        }
+
+        table
+    }
+
+    fn heap_bytes(&self) -> usize {
+        self.len() * size_of::<T>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Trie, TrieMap};
+    use std::panic::{self, AssertUnwindSafe};
+
+    const WORDS: [&str; 7] = ["coal", "cat", "cin", "catch", "cut", "cit", "camp"];
+
+    fn exercise<S: ChildStore<char>>() -> Trie<char, S> {
+        let mut trie: Trie<char, S> = WORDS.iter().collect();
+
+        assert_eq!(trie.len(), WORDS.len());
+        assert!(trie.contains("catch"));
+        assert!(!trie.contains("ca"));
+        assert_eq!(trie.words_with_prefix("ca").count(), 3);
+
+        let mut sorted = WORDS.to_vec();
+        sorted.sort();
+        assert_eq!(trie.iter().collect::<Vec<_>>(), sorted);
+
+        trie.delete("cat");
+        trie.delete("coal");
+        assert!(!trie.contains("cat"));
+        assert!(trie.contains("catch"));
+        assert!(trie.find_node("co").is_none(), "should prune \"co\"");
+        assert_eq!(trie.clone().len(), 5);
+
+        trie
+    }
+
+    #[test]
+    fn test_backends_agree() {
+        let hashed = exercise::<Hashed>();
+        let sorted = exercise::<SortedVec>();
+        let ordered = exercise::<Ordered>();
+        let ascii = exercise::<AsciiLower>();
+
+        let stats = hashed.stats();
+        for other in [sorted.stats(), ordered.stats(), ascii.stats()] {
+            assert_eq!(other.nodes, stats.nodes);
+            assert_eq!(other.branching, stats.branching);
+            assert!(other.heap_bytes > 0);
+        }
+    }
+
+    #[test]
+    fn test_sorted_vec_order() {
+        let mut table = VecChildren::default();
+
+        for key in ['m', 'c', 'x', 'c'] {
+            table.get_or_insert_with(key, || key as u32);
+        }
+        assert_eq!(table.insert('a', 0), None);
+        assert_eq!(table.remove(&'x'), Some('x' as u32));
+
+        let keys: Vec<_> = table.iter().map(|(key, _)| *key).collect();
+        assert_eq!(keys, ['a', 'c', 'm']);
+    }
+
+    #[test]
+    fn test_ascii_try_insert() {
+        let mut trie = Trie::<char, AsciiLower>::default();
+        trie.insert("cat");
+
+        assert_eq!(trie.try_insert("Cat"), Err(UnsupportedSymbol('C')));
+        assert_eq!(trie.try_insert("cat-flap"), Err(UnsupportedSymbol('-')));
+        assert_eq!(trie.try_insert("cats"), Ok(()));
+
+        // a rejected word leaves no nodes behind
+        let stats = trie.stats();
+        assert_eq!((stats.words, stats.nodes), (2, 5));
+
+        let mut map = TrieMap::<u32, char, AsciiLower>::default();
+        assert_eq!(map.try_insert("Dog", 1), Err(UnsupportedSymbol('D')));
+        assert_eq!(map.try_insert("dog", 1), Ok(None));
+        assert_eq!(map.try_insert("dog", 2), Ok(Some(1)));
+        assert!(Hashed::accepts(&'D'));
+    }
+
+    #[test]
+    #[should_panic(expected = "outside the alphabet of the child store")]
+    fn test_ascii_rejects_other_symbols() {
+        let mut trie = Trie::<char, AsciiLower>::default();
+        assert!(!trie.contains("Cat"));
+        trie.insert("Cat");
+    }
+
+    #[test]
+    fn test_ascii_panic_leaves_no_nodes() {
+        type Map = TrieMap<u32, char, AsciiLower>;
+
+        let mut map = Map::default();
+        map.insert("cat", 1);
+        let nodes = map.node_count();
+
+        let inserts: [fn(&mut Map); 3] = [
+            |map| {
+                map.insert("caT", 2);
+            },
+            |map| {
+                map.insert_weighted("cart-", 2, 5);
+            },
+            |map| {
+                map.entry("dOg").or_insert(2);
+            },
+        ];
+
+        for insert in inserts {
+            let result = panic::catch_unwind(AssertUnwindSafe(|| insert(&mut map)));
+            assert!(result.is_err());
+            assert_eq!(map.node_count(), nodes);
+            assert_eq!(map.len(), 1);
+        }
+    }
+}
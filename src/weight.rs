@@ -0,0 +1,207 @@
+use crate::map::expect_symbols;
+use crate::store::{ChildStore, Children};
+use crate::{Key, Symbol, TNode, TrieMap};
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+
+impl<V, K: Symbol, S: ChildStore<K>> TrieMap<V, K, S> {
+    /// Inserts `value` under `word` and adds `weight` to the word's ranking
+    /// weight, so re-inserting a word bumps it.
+    ///
+    /// # Panics
+    ///
+    /// If `word` holds a symbol the backend doesn't accept, like
+    /// [`insert`](Self::insert).
+    pub fn insert_weighted<Q: Key<K> + ?Sized>(
+        &mut self,
+        word: &Q,
+        value: V,
+        weight: u64,
+    ) -> Option<V> {
+        let mut symbols = word.symbols().peekable();
+        symbols.peek()?;
+        expect_symbols::<K, S>(word);
+
+        let (old, _) = Self::insert_weighted_rec(&mut self.root, &mut symbols, value, weight);
+        self.len += old.is_none() as usize;
+        old
+    }
+
+    fn insert_weighted_rec(
+        node: &mut TNode<V, K, S>,
+        word: &mut impl Iterator<Item = K>,
+        value: V,
+        weight: u64,
+    ) -> (Option<V>, u64) {
+        let (old, new_weight) = match word.next() {
+            Some(current) => {
+                let next_node = node
+                    .children
+                    .get_or_insert_with(current.clone(), || TNode::new(current, None));
+
+                Self::insert_weighted_rec(next_node, word, value, weight)
+            }
+            None => {
+                node.weight = node.weight.saturating_add(weight);
+                (node.data.replace(value), node.weight)
+            }
+        };
+
+        // weights only grow on insert, so the cached maximum can't drop
+        node.max_weight = node.max_weight.max(new_weight);
+        (old, new_weight)
+    }
+
+    pub fn weight<Q: Key<K> + ?Sized>(&self, word: &Q) -> Option<u64> {
+        self.find_node(word)
+            .filter(|node| node.is_end())
+            .map(|node| node.weight)
+    }
+
+    /// Returns the `k` heaviest words starting with `prefix`, by descending
+    /// weight and then by word.
+    ///
+    /// The search is best-first on the cached subtree maxima, so only the
+    /// branches that can still beat the current candidates are expanded.
+    pub fn top_k<Q: Key<K> + ?Sized>(&self, prefix: &Q, k: usize) -> Vec<(K::Word, u64)> {
+        let mut results = Vec::with_capacity(k);
+
+        let Some(start) = self.find_node(prefix) else {
+            return results;
+        };
+
+        let mut heap = BinaryHeap::new();
+        heap.push(Candidate {
+            weight: start.max_weight,
+            path: Reverse(prefix.symbols().collect()),
+            node: Some(start),
+        });
+
+        while results.len() < k {
+            let Some(candidate) = heap.pop() else {
+                break;
+            };
+
+            let Reverse(path) = candidate.path;
+
+            let Some(node) = candidate.node else {
+                results.push((K::to_word(&path), candidate.weight));
+                continue;
+            };
+
+            if node.is_end() {
+                heap.push(Candidate {
+                    weight: node.weight,
+                    path: Reverse(path.clone()),
+                    node: None,
+                });
+            }
+
+            for child in node.children.values() {
+                let mut path = path.clone();
+                path.push(child.value.clone());
+
+                heap.push(Candidate {
+                    weight: child.max_weight,
+                    path: Reverse(path),
+                    node: Some(child),
+                });
+            }
+        }
+
+        results
+    }
+}
+
+/// A heap entry: either a finished word (`node` is `None`) or a subtree
+/// whose words weigh at most `weight`.
+struct Candidate<'a, V, K, S: ChildStore<K>> {
+    weight: u64,
+    path: Reverse<Vec<K>>,
+    node: Option<&'a TNode<V, K, S>>,
+}
+
+impl<'a, V, K: Ord, S: ChildStore<K>> Ord for Candidate<'a, V, K, S> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.weight
+            .cmp(&other.weight)
+            .then_with(|| self.path.cmp(&other.path))
+            .then_with(|| other.node.is_some().cmp(&self.node.is_some()))
+    }
+}
+
+impl<'a, V, K: Ord, S: ChildStore<K>> PartialOrd for Candidate<'a, V, K, S> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<'a, V, K: Ord, S: ChildStore<K>> PartialEq for Candidate<'a, V, K, S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<'a, V, K: Ord, S: ChildStore<K>> Eq for Candidate<'a, V, K, S> {}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Trie, TrieMap};
+
+    fn trie() -> Trie {
+        let mut trie = Trie::new();
+
+        for (w, weight) in [
+            ("cat", 5),
+            ("car", 9),
+            ("cart", 2),
+            ("camp", 9),
+            ("dog", 20),
+        ] {
+            trie.insert_weighted(w, weight);
+        }
+
+        trie.insert("cab");
+        trie
+    }
+
+    #[test]
+    fn test_top_k() {
+        let trie = trie();
+
+        assert_eq!(
+            trie.top_k("ca", 3),
+            [
+                ("camp".to_string(), 9),
+                ("car".to_string(), 9),
+                ("cat".to_string(), 5)
+            ]
+        );
+        assert_eq!(trie.top_k("", 1), [("dog".to_string(), 20)]);
+        assert_eq!(trie.top_k("ca", 10).len(), 5);
+        assert_eq!(trie.top_k("x", 3), []);
+        assert_eq!(trie.weight("cab"), Some(0));
+        assert_eq!(trie.weight("ca"), None);
+    }
+
+    #[test]
+    fn test_weight_bump_and_delete() {
+        let mut trie = trie();
+
+        trie.insert_weighted("cart", 10);
+        assert_eq!(trie.weight("cart"), Some(12));
+        assert_eq!(trie.top_k("c", 1), [("cart".to_string(), 12)]);
+
+        trie.delete("cart");
+        assert_eq!(trie.root.max_weight, 20);
+        assert_eq!(trie.find_node("ca").unwrap().max_weight, 9);
+
+        trie.delete("dog");
+        assert_eq!(trie.root.max_weight, 9);
+
+        let mut map = TrieMap::new();
+        assert_eq!(map.insert_weighted("a", 'x', 1), None);
+        assert_eq!(map.insert_weighted("a", 'y', 1), Some('x'));
+        assert_eq!(map.weight("a"), Some(2));
+    }
+}